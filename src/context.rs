@@ -0,0 +1,373 @@
+use rug::float::{self, Constant, Round};
+use rug::ops::AssignRound;
+use rug::{Complex, Float, Integer};
+use std::cmp::Ordering;
+use std::f64::consts::LOG2_10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Angle {
+    Radian,
+    Degree,
+    Gradian,
+}
+
+impl Angle {
+    fn half_turn(self) -> Option<u32> {
+        match self {
+            Angle::Radian => None,
+            Angle::Degree => Some(180),
+            Angle::Gradian => Some(200),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    Real,
+    Complex,
+    Integer,
+    Rational,
+    Programmer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Implicit {
+    Standard,
+    Tight,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Width {
+    W8,
+    W16,
+    W32,
+    W64,
+    W128,
+}
+
+impl Width {
+    pub fn bits(self) -> u32 {
+        match self {
+            Width::W8 => 8,
+            Width::W16 => 16,
+            Width::W32 => 32,
+            Width::W64 => 64,
+            Width::W128 => 128,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fraction {
+    Improper,
+    Mixed,
+    Decimal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Modulo {
+    Truncated,
+    Floored,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rounding {
+    HalfUp,
+    HalfEven,
+    Zero,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Context {
+    prec: u32,
+    round: Round,
+    rule: Rounding,
+    modulo: Modulo,
+    angle: Angle,
+    mode: Mode,
+    polar: bool,
+    fraction: Fraction,
+    radix: i32,
+    width: Width,
+    signed: bool,
+    implicit: Implicit,
+    legacy: bool,
+    log2: bool,
+    max_exp: u32,
+    max_bits: u32,
+    max: Float,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context::new(2560)
+    }
+}
+
+impl Context {
+    pub fn new(prec: u32) -> Self {
+        let prec = prec.max(float::prec_min()).min(float::prec_max());
+        Self {
+            prec,
+            round: Round::Nearest,
+            rule: Rounding::HalfUp,
+            modulo: Modulo::Truncated,
+            angle: Angle::Radian,
+            mode: Mode::Real,
+            polar: false,
+            fraction: Fraction::Improper,
+            radix: 10,
+            width: Width::W64,
+            signed: true,
+            implicit: Implicit::Standard,
+            legacy: false,
+            log2: false,
+            max_exp: 768,
+            max_bits: 1 << 20,
+            max: Context::bound(prec, 768),
+        }
+    }
+
+    pub fn with_digits(digits: u32) -> Self {
+        Context::new((digits as f64 * LOG2_10).ceil() as u32)
+    }
+
+    pub fn round(mut self, round: Round) -> Self {
+        self.round = round;
+        self
+    }
+
+    pub fn round_rule(mut self, rule: Rounding) -> Self {
+        self.rule = rule;
+        self
+    }
+
+    pub fn modulo(mut self, modulo: Modulo) -> Self {
+        self.modulo = modulo;
+        self
+    }
+
+    pub fn angle(mut self, angle: Angle) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn polar(mut self, polar: bool) -> Self {
+        self.polar = polar;
+        self
+    }
+
+    pub fn fraction(mut self, fraction: Fraction) -> Self {
+        self.fraction = fraction;
+        self
+    }
+
+    pub fn radix(mut self, radix: i32) -> Self {
+        self.radix = radix;
+        self
+    }
+
+    pub fn width(mut self, width: Width) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn signed(mut self, signed: bool) -> Self {
+        self.signed = signed;
+        self
+    }
+
+    pub fn implicit(mut self, implicit: Implicit) -> Self {
+        self.implicit = implicit;
+        self
+    }
+
+    pub fn legacy(mut self, legacy: bool) -> Self {
+        self.legacy = legacy;
+        self
+    }
+
+    pub fn log_base2(mut self, log2: bool) -> Self {
+        self.log2 = log2;
+        self
+    }
+
+    pub fn bounds(mut self, max_exp: u32) -> Self {
+        self.max_exp = max_exp;
+        self.max = Context::bound(self.prec, max_exp);
+        self
+    }
+
+    pub fn integer_bits(mut self, max_bits: u32) -> Self {
+        self.max_bits = max_bits;
+        self
+    }
+
+    pub fn prec(&self) -> u32 {
+        self.prec
+    }
+
+    pub fn rounding(&self) -> Round {
+        self.round
+    }
+
+    pub fn rounding_rule(&self) -> Rounding {
+        self.rule
+    }
+
+    pub fn modulo_style(&self) -> Modulo {
+        self.modulo
+    }
+
+    pub fn angle_unit(&self) -> Angle {
+        self.angle
+    }
+
+    pub fn number_mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn is_polar(&self) -> bool {
+        self.polar
+    }
+
+    pub fn fraction_style(&self) -> Fraction {
+        self.fraction
+    }
+
+    pub fn output_radix(&self) -> i32 {
+        self.radix
+    }
+
+    pub fn word_width(&self) -> Width {
+        self.width
+    }
+
+    pub fn is_signed(&self) -> bool {
+        self.signed
+    }
+
+    pub fn implicit_product(&self) -> Implicit {
+        self.implicit
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.legacy
+    }
+
+    pub fn is_log_base2(&self) -> bool {
+        self.log2
+    }
+
+    pub fn max_exp(&self) -> u32 {
+        self.max_exp
+    }
+
+    pub fn max_bits(&self) -> u32 {
+        self.max_bits
+    }
+
+    pub(crate) fn max(&self) -> &Float {
+        &self.max
+    }
+
+    pub(crate) fn float<T>(&self, val: T) -> Float
+    where Float: AssignRound<T, Round = Round, Ordering = Ordering> {
+        Float::with_val_round(self.prec, val, self.round).0
+    }
+
+    pub(crate) fn complex<T>(&self, val: T) -> Complex
+    where Complex: AssignRound<T, Round = (Round, Round), Ordering = (Ordering, Ordering)> {
+        Complex::with_val_round(self.prec, val, (self.round, self.round)).0
+    }
+
+    pub(crate) fn convert(&self, v: &Float, from: Angle, to: Angle) -> Float {
+        let prec = self.prec + 64;
+        let mut res = Float::with_val(prec, v);
+        if from != to {
+            let pi = Float::with_val(prec, Constant::Pi);
+            match from.half_turn() {
+                Some(half) => res /= half,
+                None => res /= &pi,
+            }
+            match to.half_turn() {
+                Some(half) => res *= half,
+                None => res *= &pi,
+            }
+        }
+        res
+    }
+
+    pub(crate) fn radians(&self, v: &Float) -> (Float, Option<usize>) {
+        let half = match self.angle.half_turn() {
+            Some(half) => half,
+            None => return (v.clone(), if *v == 0.0 { Some(0) } else { None }),
+        };
+
+        let prec = self.prec + 64;
+        let turn = Float::with_val(prec, 2 * half);
+        let mut m = Float::with_val(prec, v / &turn);
+        m.floor_mut();
+        let mut r = Float::with_val(prec, v - &m * &turn);
+        if r < 0.0 {
+            r += &turn;
+        } else if r >= turn {
+            r -= &turn;
+        }
+
+        let twice = Float::with_val(prec, &r * 2u32);
+        let mut k = Float::with_val(prec, &twice / half);
+        k.round_mut();
+        let quarter = match k.to_u32_saturating() {
+            Some(n) if Float::with_val(prec, &k * half) == twice => Some(n as usize % 4),
+            _ => None,
+        };
+        (self.convert(&r, self.angle, Angle::Radian), quarter)
+    }
+
+    pub(crate) fn arc<T>(&self, val: T) -> Float
+    where Float: AssignRound<T, Round = Round, Ordering = Ordering> {
+        if self.angle == Angle::Radian {
+            return self.float(val);
+        }
+        let rad = Float::with_val_round(self.prec + 64, val, Round::Nearest).0;
+        let mut res = self.convert(&rad, Angle::Radian, self.angle);
+        res.set_prec_round(self.prec, self.round);
+        res
+    }
+
+    pub(crate) fn scale(&self, z: &Complex, from: Angle, to: Angle) -> Complex {
+        let prec = self.prec + 64;
+        let factor = self.convert(&Float::with_val(prec, 1), from, to);
+        Complex::with_val(prec, z * &factor)
+    }
+
+    pub(crate) fn arc_complex<T>(&self, val: T) -> Complex
+    where Complex: AssignRound<T, Round = (Round, Round), Ordering = (Ordering, Ordering)> {
+        if self.angle == Angle::Radian {
+            return self.complex(val);
+        }
+        let rad = Complex::with_val_round(self.prec + 64, val, (Round::Nearest, Round::Nearest)).0;
+        let mut res = self.scale(&rad, Angle::Radian, self.angle);
+        res.set_prec_round(self.prec, (self.round, self.round));
+        res
+    }
+
+    pub(crate) fn wrap(&self, n: Integer) -> Integer {
+        let bits = self.width.bits();
+        let mut n = n.keep_bits(bits);
+        if self.signed && n.get_bit(bits - 1) {
+            n -= Integer::from(1) << bits;
+        }
+        n
+    }
+
+    fn bound(prec: u32, max_exp: u32) -> Float {
+        Float::with_val(prec, Float::u_pow_u(10, max_exp))
+    }
+}
@@ -0,0 +1,80 @@
+use crate::token::Span;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CalcError {
+    EmptyExpression,
+    Syntax { span: Span },
+    UnknownOperator { span: Span },
+    UnknownFunction { name: String, span: Span },
+    UnknownVariable { name: String, span: Span },
+    Reserved { name: String, span: Span },
+    InvalidNumber { span: Span },
+    BeyondAccuracy { span: Span },
+    Overflow { op: String, span: Span },
+    DivideByZero { op: String, span: Span },
+    Domain { name: String, span: Span },
+    Arity { name: String, span: Span },
+    Recursion { name: String, span: Span },
+    TooDeep { span: Span },
+    Digits,
+    Radix,
+}
+
+impl CalcError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            CalcError::Syntax { span } |
+            CalcError::UnknownOperator { span } |
+            CalcError::UnknownFunction { span, .. } |
+            CalcError::UnknownVariable { span, .. } |
+            CalcError::Reserved { span, .. } |
+            CalcError::InvalidNumber { span } |
+            CalcError::BeyondAccuracy { span } |
+            CalcError::Overflow { span, .. } |
+            CalcError::DivideByZero { span, .. } |
+            CalcError::Domain { span, .. } |
+            CalcError::Arity { span, .. } |
+            CalcError::Recursion { span, .. } |
+            CalcError::TooDeep { span } => Some(span.clone()),
+            CalcError::EmptyExpression | CalcError::Digits | CalcError::Radix => None,
+        }
+    }
+
+    pub(crate) fn with_operator(self, op: &str) -> CalcError {
+        match self {
+            CalcError::BeyondAccuracy { span } | CalcError::Overflow { span, .. } => {
+                CalcError::Overflow { op: op.to_string(), span }
+            },
+            CalcError::DivideByZero { span, .. } => CalcError::DivideByZero { op: op.to_string(), span },
+            CalcError::Domain { span, .. } => CalcError::Domain { name: op.to_string(), span },
+            err => err,
+        }
+    }
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match self {
+            CalcError::EmptyExpression => "Empty Expression",
+            CalcError::Syntax { .. } => "Expression Error",
+            CalcError::UnknownOperator { .. } => "Operator Undefined",
+            CalcError::UnknownFunction { .. } => "Function Undefined",
+            CalcError::UnknownVariable { .. } => "Variable Undefined",
+            CalcError::Reserved { .. } => "Name Reserved",
+            CalcError::InvalidNumber { .. } => "Invalid Number",
+            CalcError::BeyondAccuracy { .. } | CalcError::Overflow { .. } => "Beyond Accuracy",
+            CalcError::DivideByZero { .. } => "Divide By Zero",
+            CalcError::Domain { .. } => "Parameter Error",
+            CalcError::Arity { .. } => "Argument Error",
+            CalcError::Recursion { .. } => "Recursion Too Deep",
+            CalcError::TooDeep { .. } => "Nesting Too Deep",
+            CalcError::Digits => "Set Accuracy Greater Than 1",
+            CalcError::Radix => "Set Radix Between 2 And 36",
+        };
+        f.write_str(msg)
+    }
+}
+
+impl Error for CalcError {}
@@ -0,0 +1,346 @@
+use crate::context::{Context, Implicit, Mode};
+use crate::error::CalcError;
+use crate::func::{arity, is_constant};
+use crate::session::Session;
+use crate::token::{tokenize, Span, Token};
+use crate::value::Value;
+use crate::{Other, Symbol};
+use rug::Float;
+
+const MAX_DEPTH: usize = 1000;
+
+fn reserved(name: &str, mode: Mode) -> bool {
+    arity(name).is_some() || is_constant(name, mode)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Number(String, Span),
+    Const(String, Span),
+    Var(String, Span),
+    Neg(Box<Expr>),
+    Not(Span, Box<Expr>),
+    Binary(u8, Span, Box<Expr>, Box<Expr>),
+    Call(String, Span, Vec<Expr>),
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        let mut expr = self;
+        loop {
+            match expr {
+                Expr::Neg(value) => expr = value,
+                Expr::Number(_, span) | Expr::Const(_, span) | Expr::Var(_, span) |
+                Expr::Not(span, _) | Expr::Binary(_, span, ..) | Expr::Call(_, span, _) => return span.clone(),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Stmt {
+    Expr(Expr),
+    Assign(String, Expr),
+    Define(String, Vec<String>, Expr),
+}
+
+struct Parser<'a> {
+    tokens: &'a [(Token, Span)],
+    index: usize,
+    depth: usize,
+    end: usize,
+    implicit: Implicit,
+    legacy: bool,
+    mode: Mode,
+}
+
+pub fn parse(expr: &str) -> Result<Expr, CalcError> {
+    parse_with(expr, &Context::default())
+}
+
+pub fn parse_with(expr: &str, ctx: &Context) -> Result<Expr, CalcError> {
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return Err(CalcError::EmptyExpression);
+    }
+
+    let mut parser = Parser::new(&tokens, expr.len(), ctx);
+    parser.finish()
+}
+
+pub fn parse_statement(input: &str) -> Result<Stmt, CalcError> {
+    parse_statement_with(input, &Context::default())
+}
+
+pub fn parse_statement_with(input: &str, ctx: &Context) -> Result<Stmt, CalcError> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err(CalcError::EmptyExpression);
+    }
+
+    let mut parser = Parser::new(&tokens, input.len(), ctx);
+    if let [(Token::Ident(name), span), (Token::Assign, _), _, ..] = tokens.as_slice() {
+        if reserved(name, ctx.number_mode()) {
+            return Err(CalcError::Reserved { name: name.clone(), span: span.clone() });
+        }
+        parser.index = 2;
+        return Ok(Stmt::Assign(name.clone(), parser.finish()?));
+    }
+    if let Some((name, params)) = parser.signature()? {
+        return Ok(Stmt::Define(name, params, parser.finish()?));
+    }
+    Ok(Stmt::Expr(parser.finish()?))
+}
+
+fn number(text: &str, span: &Span) -> Result<Expr, CalcError> {
+    let text = text.to_string();
+    let (radix, digits) = text.radix();
+    match Float::parse_radix(digits, radix) {
+        Ok(_) => Ok(Expr::Number(text.clone(), span.clone())),
+        Err(_) => Err(CalcError::InvalidNumber { span: span.clone() })
+    }
+}
+
+fn percent(next: Option<&(Token, Span)>) -> bool {
+    match next {
+        None => true,
+        Some((Token::Operator(ch), _)) => *ch != b'-' && *ch != b'~',
+        Some((token, _)) => matches!(token, Token::RParen | Token::Comma | Token::Assign),
+    }
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [(Token, Span)], end: usize, ctx: &Context) -> Self {
+        Parser {
+            tokens, index: 0, depth: 0, end,
+            implicit: ctx.implicit_product(), legacy: ctx.is_legacy(), mode: ctx.number_mode(),
+        }
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.index).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<&'a (Token, Span)> {
+        let token = self.tokens.get(self.index);
+        self.index += 1;
+        token
+    }
+
+    fn span(&self) -> Span {
+        match self.tokens.get(self.index) {
+            Some((_, span)) => span.clone(),
+            None => self.end..self.end,
+        }
+    }
+
+    fn error(&self) -> CalcError {
+        CalcError::Syntax { span: self.span() }
+    }
+
+    fn finish(&mut self) -> Result<Expr, CalcError> {
+        let tree = self.binary(1)?;
+        if self.peek() == Some(&Token::Assign) && self.index + 1 == self.tokens.len() {
+            self.index += 1;
+        }
+        if self.index < self.tokens.len() {
+            return Err(self.error());
+        }
+        Ok(tree)
+    }
+
+    fn signature(&mut self) -> Result<Option<(String, Vec<String>)>, CalcError> {
+        let (name, span) = match self.tokens {
+            [(Token::Ident(name), span), (Token::LParen, _), ..] => (name, span),
+            _ => return Ok(None),
+        };
+
+        let mut params: Vec<(&String, &Span)> = Vec::new();
+        let mut index = 2;
+        loop {
+            match self.tokens.get(index) {
+                Some((Token::RParen, _)) if params.is_empty() => break,
+                Some((Token::Ident(param), span)) => {
+                    params.push((param, span));
+                    index += 1;
+                }
+                _ => return Ok(None),
+            }
+            match self.tokens.get(index) {
+                Some((Token::Comma, _)) => index += 1,
+                Some((Token::RParen, _)) => break,
+                _ => return Ok(None),
+            }
+        }
+        match self.tokens.get(index + 1..index + 3) {
+            Some([(Token::Assign, _), _]) => self.index = index + 2,
+            _ => return Ok(None),
+        }
+
+        if reserved(name, self.mode) {
+            return Err(CalcError::Reserved { name: name.clone(), span: span.clone() });
+        }
+        for (i, (param, span)) in params.iter().enumerate() {
+            if reserved(param, self.mode) {
+                return Err(CalcError::Reserved { name: param.to_string(), span: (*span).clone() });
+            } else if params[..i].iter().any(|(other, _)| other == param) {
+                return Err(CalcError::Syntax { span: (*span).clone() });
+            }
+        }
+        let params = params.into_iter().map(|(param, _)| param.clone()).collect();
+        Ok(Some((name.clone(), params)))
+    }
+
+    fn enter(&mut self) -> Result<(), CalcError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(CalcError::TooDeep { span: self.span() });
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), CalcError> {
+        if self.peek() != Some(&token) {
+            return Err(self.error());
+        }
+        self.index += 1;
+        Ok(())
+    }
+
+    fn binary(&mut self, min: u8) -> Result<Expr, CalcError> {
+        let depth = self.depth;
+        self.enter()?;
+        let mut left = self.unary()?;
+        loop {
+            let (ch, priority, span) = match self.tokens.get(self.index) {
+                Some((Token::Operator(ch), span)) => (*ch, ch.priority(), span),
+                Some((Token::Ident(_), span)) | Some((Token::LParen, span)) => {
+                    let priority = match self.implicit {
+                        Implicit::Standard => b'*'.priority(),
+                        Implicit::Tight => b'*'.priority() + 1,
+                    };
+                    (b'*', priority, span)
+                }
+                Some((Token::Number(_), _)) => return Err(self.error()),
+                _ => break,
+            };
+            if priority < min {
+                break;
+            }
+            self.enter()?;
+            if let Some(Token::Operator(_)) = self.peek() {
+                self.index += 1;
+            }
+            let right = match ch {
+                b'^' if !self.legacy => self.binary(priority)?,
+                _ => self.binary(priority + 1)?,
+            };
+            left = Expr::Binary(ch, span.clone(), Box::new(left), Box::new(right));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, CalcError> {
+        if let (Some(Token::Operator(b'-')), false) = (self.peek(), self.legacy) {
+            self.enter()?;
+            self.index += 1;
+            return Ok(Expr::Neg(Box::new(self.binary(b'^'.priority())?)));
+        } else if let Some(Token::Operator(b'-')) = self.peek() {
+            self.index += 1;
+            let locat = self.index;
+            return match self.primary()? {
+                value @ Expr::Number(..) | value @ Expr::Const(..) => Ok(Expr::Neg(Box::new(value))),
+                _ => {
+                    self.index = locat;
+                    Err(self.error())
+                }
+            };
+        } else if let Some((Token::Operator(b'~'), span)) = self.tokens.get(self.index) {
+            self.enter()?;
+            self.index += 1;
+            return Ok(Expr::Not(span.clone(), Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    fn postfix(&mut self) -> Result<Expr, CalcError> {
+        let mut value = self.primary()?;
+        loop {
+            if let Some(Token::Operator(b'!')) | Some(Token::Operator(b'%')) = self.peek() {
+                self.enter()?;
+            }
+            value = match &self.tokens[self.index..] {
+                [(Token::Operator(b'!'), first), (Token::Operator(b'!'), second), ..] if first.end == second.start => {
+                    self.index += 2;
+                    Expr::Call("dfac".to_string(), first.start..second.end, vec![value])
+                }
+                [(Token::Operator(b'!'), span), ..] => {
+                    self.index += 1;
+                    Expr::Call("fac".to_string(), span.clone(), vec![value])
+                }
+                [(Token::Operator(b'%'), span), rest @ ..] if percent(rest.first()) => {
+                    self.index += 1;
+                    let hundred = Expr::Number("100".to_string(), span.clone());
+                    Expr::Binary(b'/', span.clone(), Box::new(value), Box::new(hundred))
+                }
+                _ => return Ok(value),
+            };
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, CalcError> {
+        let locat = self.index;
+        match self.next() {
+            Some((Token::Number(text), span)) => number(text, span),
+            Some((Token::Ident(name), span)) => {
+                let constant = is_constant(name, self.mode) && arity(name).is_none();
+                if self.peek() != Some(&Token::LParen) || constant {
+                    if is_constant(name, self.mode) {
+                        return Ok(Expr::Const(name.clone(), span.clone()));
+                    } else if reserved(name, self.mode) {
+                        return Err(self.error());
+                    }
+                    return Ok(Expr::Var(name.clone(), span.clone()));
+                }
+
+                self.index += 1;
+                let mut args = Vec::new();
+                if self.peek() == Some(&Token::RParen) {
+                    self.index += 1;
+                } else {
+                    loop {
+                        args.push(self.binary(1)?);
+                        if self.peek() != Some(&Token::Comma) {
+                            break;
+                        }
+                        self.index += 1;
+                    }
+                    self.expect(Token::RParen)?;
+                }
+
+                if let Some(valid) = arity(name) {
+                    if !valid.accepts(args.len()) {
+                        return Err(CalcError::Arity { name: name.clone(), span: span.clone() });
+                    }
+                }
+                Ok(Expr::Call(name.clone(), span.clone(), args))
+            }
+            Some((Token::LParen, _)) => {
+                let inner = self.binary(1)?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            _ => {
+                self.index = locat;
+                Err(self.error())
+            }
+        }
+    }
+}
+
+impl Expr {
+    pub fn eval(&self, ctx: &Context) -> Result<Value, CalcError> {
+        Session::with_context(ctx.clone()).eval(self)
+    }
+}
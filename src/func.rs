@@ -0,0 +1,87 @@
+use crate::context::{Context, Mode};
+use rug::{float::Constant, Float};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Arity {
+    Exact(usize),
+    Range(usize, usize),
+    Variadic(usize),
+}
+
+impl Arity {
+    pub fn accepts(self, n: usize) -> bool {
+        match self {
+            Arity::Exact(valid) => n == valid,
+            Arity::Range(min, max) => n >= min && n <= max,
+            Arity::Variadic(min) => n >= min,
+        }
+    }
+}
+
+const FUNCTIONS: [(&str, Arity); 79] = [
+    ("abs", Arity::Exact(1)), ("cos", Arity::Exact(1)), ("sin", Arity::Exact(1)),
+    ("tan", Arity::Exact(1)), ("csc", Arity::Exact(1)), ("sec", Arity::Exact(1)),
+    ("cot", Arity::Exact(1)), ("coth", Arity::Exact(1)), ("cosh", Arity::Exact(1)),
+    ("sinh", Arity::Exact(1)), ("tanh", Arity::Exact(1)), ("sech", Arity::Exact(1)),
+    ("ln", Arity::Exact(1)), ("csch", Arity::Exact(1)), ("acos", Arity::Exact(1)),
+    ("asin", Arity::Exact(1)), ("atan", Arity::Exact(1)), ("acosh", Arity::Exact(1)),
+    ("asinh", Arity::Exact(1)), ("atanh", Arity::Exact(1)), ("exp", Arity::Exact(1)),
+    ("log", Arity::Range(1, 2)), ("logx", Arity::Exact(1)), ("sqrt", Arity::Exact(1)),
+    ("cbrt", Arity::Exact(1)), ("fac", Arity::Exact(1)), ("atan2", Arity::Exact(2)),
+    ("hypot", Arity::Exact(2)), ("root", Arity::Exact(2)), ("round", Arity::Range(1, 2)),
+    ("min", Arity::Variadic(1)), ("max", Arity::Variadic(1)), ("gcd", Arity::Variadic(2)),
+    ("lcm", Arity::Variadic(2)), ("deg", Arity::Exact(1)),
+    ("rad", Arity::Exact(1)), ("dfac", Arity::Exact(1)), ("gamma", Arity::Exact(1)),
+    ("lgamma", Arity::Exact(1)), ("digamma", Arity::Exact(1)), ("beta", Arity::Exact(2)),
+    ("erf", Arity::Exact(1)), ("erfc", Arity::Exact(1)), ("zeta", Arity::Exact(1)),
+    ("j0", Arity::Exact(1)), ("j1", Arity::Exact(1)), ("jn", Arity::Exact(2)),
+    ("y0", Arity::Exact(1)), ("y1", Arity::Exact(1)), ("yn", Arity::Exact(2)),
+    ("agm", Arity::Exact(2)), ("eint", Arity::Exact(1)), ("li2", Arity::Exact(1)),
+    ("lambertw", Arity::Range(1, 2)), ("nCr", Arity::Exact(2)), ("nPr", Arity::Exact(2)),
+    ("isprime", Arity::Exact(1)), ("nextprime", Arity::Exact(1)), ("factor", Arity::Exact(1)),
+    ("modinv", Arity::Exact(2)), ("powmod", Arity::Exact(3)), ("floor", Arity::Exact(1)),
+    ("ceil", Arity::Exact(1)), ("trunc", Arity::Exact(1)), ("frac", Arity::Exact(1)),
+    ("sign", Arity::Exact(1)), ("mod", Arity::Exact(2)), ("rem", Arity::Exact(2)),
+    ("log10", Arity::Exact(1)), ("log2", Arity::Exact(1)), ("lg", Arity::Exact(1)),
+    ("acsc", Arity::Exact(1)), ("asec", Arity::Exact(1)), ("acot", Arity::Exact(1)),
+    ("acsch", Arity::Exact(1)), ("asech", Arity::Exact(1)), ("acoth", Arity::Exact(1)),
+    ("versin", Arity::Exact(1)), ("haversine", Arity::Exact(1)),
+];
+
+pub fn arity(name: &str) -> Option<Arity> {
+    FUNCTIONS.iter().find(|(valid, _)| *valid == name).map(|&(_, arity)| arity)
+}
+
+const EXACT: [&str; 9] = ["nCr", "nPr", "gcd", "lcm", "isprime", "nextprime", "factor", "modinv", "powmod"];
+
+pub(crate) fn is_exact(name: &str) -> bool {
+    EXACT.contains(&name)
+}
+
+const CONSTANTS: [&str; 6] = ["P", "e", "phi", "gamma", "catalan", "ln2"];
+
+pub(crate) fn is_constant(name: &str, mode: Mode) -> bool {
+    CONSTANTS.contains(&name) || name == "i" && mode == Mode::Complex
+}
+
+pub(crate) fn constant(name: &str, ctx: &Context) -> Option<Float> {
+    let value = match name {
+        "P" => ctx.float(Constant::Pi),
+        "gamma" => ctx.float(Constant::Euler),
+        "catalan" => ctx.float(Constant::Catalan),
+        "ln2" => ctx.float(Constant::Log2),
+        "e" => {
+            let one = Float::with_val(ctx.prec(), 1);
+            ctx.float(one.exp_ref())
+        }
+        "phi" => {
+            let mut phi = Float::with_val(ctx.prec() + 64, 5);
+            phi.sqrt_mut();
+            phi += 1;
+            phi /= 2;
+            ctx.float(&phi)
+        }
+        _ => return None,
+    };
+    Some(value)
+}
@@ -1,229 +1,562 @@
-use rug::ops::Pow;
-use rug::{float::Constant, Float};
-use std::{char::from_digit, cell::RefCell};
-use std::collections::HashMap;
-use std::process::exit;
-use lazy_static::lazy_static;
-
-#[derive(Clone)]
-enum Sign {
-    Init,
-    Data,
-    Char
-}
+use rug::float::Constant;
+use rug::integer::IsPrime;
+use rug::ops::{DivRounding, Pow, RemRounding};
+use rug::{Complex, Float, Integer, Rational};
+use std::cmp::Ordering;
+use std::f64::consts::LOG2_10;
+
+const PRIME_BITS: u32 = 4096;
+const FACTOR_BITS: u32 = 128;
+
+mod context;
+mod error;
+mod expr;
+mod func;
+mod session;
+mod token;
+mod value;
+
+pub use context::{Angle, Context, Fraction, Implicit, Mode, Modulo, Rounding, Width};
+pub use error::CalcError;
+pub use expr::{parse, parse_statement, parse_statement_with, parse_with, Expr, Stmt};
+pub use func::{arity, Arity};
+pub use session::{Function, Session};
+pub use token::{tokenize, Span, Token};
+pub use value::Value;
 
 pub struct Calc {
-    sign: RefCell<Sign>,
-    numbers: RefCell<Vec<Float>>,
-    operator: RefCell<Vec<u8>>,
-    func: RefCell<HashMap<u32, String>>,
     expression: String,
-}
-
-#[macro_use]
-lazy_static! {
-    static ref MAX: Float = {
-        let max = Float::parse("1e+768").unwrap();
-        Float::with_val(2560, max)
-    };
-    static ref MIN: Float = {
-        let min = Float::parse("-1e+768").unwrap();
-        Float::with_val(2560, min)
-    };
+    context: Context,
 }
 
 trait Symbol {
     fn priority(&self) -> u8;
-    fn computing(&self, n: &Calc) -> Result<Float, String>;
+    fn name(&self) -> &'static str;
+    fn computing(&self, c2: Float, c1: Float, ctx: &Context, span: &Span) -> Result<Float, CalcError>;
+    fn complex(&self, c2: Complex, c1: Complex, ctx: &Context, span: &Span) -> Result<Complex, CalcError>;
+    fn integer(&self, c2: &Integer, c1: &Integer, ctx: &Context, span: &Span) -> Option<Result<Integer, CalcError>>;
+    fn rational(&self, c2: &Rational, c1: &Rational, ctx: &Context, span: &Span) -> Option<Result<Rational, CalcError>>;
 }
 
 trait Bignum {
-    fn fmod(&self, n: &Float) -> Float;
-    fn accuracy(self) -> Result<Float, String>;
-    fn to_string_round(&self, n: Option<usize>) -> String;
+    fn fmod(&self, n: &Float, floored: bool, ctx: &Context) -> Float;
+    fn accuracy(self, ctx: &Context, span: &Span) -> Result<Float, CalcError>;
+    fn round_by(&self, ctx: &Context) -> Float;
+    fn round_digits(&self, digits: i32, ctx: &Context) -> Float;
+    fn lambert_w(&self, branch: i32, ctx: &Context) -> Option<Float>;
+    fn to_string_round(&self, n: Option<usize>, radix: i32) -> String;
+}
+
+trait Bigcomplex {
+    fn accuracy(self, ctx: &Context, span: &Span) -> Result<Complex, CalcError>;
+    fn to_string_round(&self, n: Option<usize>, radix: i32) -> String;
+    fn to_polar_round(&self, n: Option<usize>, ctx: &Context) -> String;
+}
+
+trait Bigint {
+    fn accuracy(self, ctx: &Context, span: &Span) -> Result<Integer, CalcError>;
+    fn power(&self, e: &Integer, ctx: &Context, span: &Span) -> Result<Integer, CalcError>;
+    fn factor(&self, span: &Span) -> Result<Vec<(Integer, u32)>, CalcError>;
+    fn rho(&self) -> Option<Integer>;
+}
+
+trait Bigrational {
+    fn accuracy(self, ctx: &Context, span: &Span) -> Result<Rational, CalcError>;
+    fn round_by(&self, ctx: &Context) -> Rational;
+    fn round_digits(&self, digits: i32, ctx: &Context) -> Rational;
+    fn to_string_round(&self, n: Option<usize>, ctx: &Context) -> String;
 }
 
 trait Other {
-    fn to_fixed(&self) -> String;
+    fn to_fixed(&self, radix: i32) -> String;
+    fn round_fixed(self, places: usize, radix: i32) -> String;
     fn clean_zero(self) -> String;
-    fn math(&self, v: Float) -> Result<Float, String>;
-    fn extract(&self, n: usize, i: usize) -> Result<Float, String>;
+    fn math(&self, v: Float, ctx: &Context, span: &Span) -> Result<Float, CalcError>;
+    fn trig(&self, v: Float, ctx: &Context, span: &Span) -> Result<Float, CalcError>;
+    fn call(&self, args: Vec<Float>, ctx: &Context, span: &Span) -> Result<Float, CalcError>;
+    fn math_complex(&self, v: Complex, ctx: &Context, span: &Span) -> Result<Complex, CalcError>;
+    fn call_complex(&self, args: Vec<Complex>, ctx: &Context, span: &Span) -> Result<Complex, CalcError>;
+    fn call_integer(&self, args: &[Integer], ctx: &Context, span: &Span) -> Option<Result<Integer, CalcError>>;
+    fn call_rational(&self, args: &[Rational], ctx: &Context, span: &Span) -> Option<Result<Rational, CalcError>>;
+    fn radix(&self) -> (i32, &str);
+    fn extract(&self, ctx: &Context, span: &Span) -> Result<Float, CalcError>;
+    fn fraction(&self, ctx: &Context, span: &Span) -> Result<Rational, CalcError>;
 }
 
 impl Symbol for u8 {
     fn priority(&self) -> u8 {
         match self {
-            b'+' | b'-' => 1,
-            b'*' | b'/' | b'%' => 2,
-            b'^' => 3,
-            _ => exit(0)
+            b'|' => 1,
+            b'x' => 2,
+            b'&' => 3,
+            b'<' | b'>' => 4,
+            b'+' | b'-' => 5,
+            b'*' | b'/' | b'%' | b'\\' | b'm' => 6,
+            b'^' => 8,
+            _ => 0
         }
     }
 
-    fn computing(&self, num: &Calc) -> Result<Float, String> {
-        let c1 = num.numbers.borrow_mut().pop().unwrap();
-        let c2 = num.numbers.borrow_mut().pop().unwrap();
+    fn name(&self) -> &'static str {
         match self {
-            b'+' => Float::with_val(2560, &c2 + &c1).accuracy(),
-            b'-' => Float::with_val(2560, &c2 - &c1).accuracy(),
-            b'*' => Float::with_val(2560, &c2 * &c1).accuracy(),
-            b'/' if &c1 != &0.0 => Float::with_val(2560, &c2 / &c1).accuracy(),
-            b'%' if &c1 != &0.0 => c2.fmod(&c1).accuracy(),
-            b'^' => Float::with_val(2560, &c2.pow(&c1)).accuracy(),
-            _ => Err("Divide By Zero".to_string())
+            b'+' => "+", b'-' => "-", b'*' => "*", b'/' => "/", b'%' => "%", b'^' => "^",
+            b'\\' => "//", b'm' => "mod", b'&' => "&", b'|' => "|", b'x' => "xor",
+            b'<' => "<<", b'>' => ">>", b'~' => "~",
+            _ => "?"
         }
     }
+
+    fn computing(&self, c2: Float, c1: Float, ctx: &Context, span: &Span) -> Result<Float, CalcError> {
+        match self {
+            b'+' => ctx.float(&c2 + &c1).accuracy(ctx, span),
+            b'-' => ctx.float(&c2 - &c1).accuracy(ctx, span),
+            b'*' => ctx.float(&c2 * &c1).accuracy(ctx, span),
+            b'/' | b'%' | b'\\' | b'm' if c1 == 0.0 => {
+                Err(CalcError::DivideByZero { op: self.name().to_string(), span: span.clone() })
+            },
+            b'/' => ctx.float(&c2 / &c1).accuracy(ctx, span),
+            b'\\' => {
+                let mut q = Float::with_val(ctx.prec() + 64, &c2 / &c1);
+                q.floor_mut();
+                ctx.float(&q).accuracy(ctx, span)
+            },
+            b'%' | b'm' => c2.fmod(&c1, *self == b'm', ctx).accuracy(ctx, span),
+            b'^' => ctx.float((&c2).pow(&c1)).accuracy(ctx, span),
+            _ => Err(CalcError::UnknownOperator { span: span.clone() })
+        }
+    }
+
+    fn complex(&self, c2: Complex, c1: Complex, ctx: &Context, span: &Span) -> Result<Complex, CalcError> {
+        match self {
+            b'+' => ctx.complex(&c2 + &c1).accuracy(ctx, span),
+            b'-' => ctx.complex(&c2 - &c1).accuracy(ctx, span),
+            b'*' => ctx.complex(&c2 * &c1).accuracy(ctx, span),
+            b'/' if c1.real().is_zero() && c1.imag().is_zero() => {
+                Err(CalcError::DivideByZero { op: self.name().to_string(), span: span.clone() })
+            },
+            b'/' => ctx.complex(&c2 / &c1).accuracy(ctx, span),
+            b'^' => ctx.complex((&c2).pow(&c1)).accuracy(ctx, span),
+            b'%' | b'\\' | b'm' => Err(CalcError::Domain { name: self.name().to_string(), span: span.clone() }),
+            _ => Err(CalcError::UnknownOperator { span: span.clone() })
+        }
+    }
+
+    fn integer(&self, c2: &Integer, c1: &Integer, ctx: &Context, span: &Span) -> Option<Result<Integer, CalcError>> {
+        let word = ctx.number_mode() == Mode::Programmer;
+        let res = match self {
+            b'+' => Integer::from(c2 + c1),
+            b'-' => Integer::from(c2 - c1),
+            b'*' => Integer::from(c2 * c1),
+            b'/' | b'%' | b'\\' | b'm' if *c1 == 0 => {
+                return Some(Err(CalcError::DivideByZero { op: self.name().to_string(), span: span.clone() }));
+            },
+            b'/' if c2.is_divisible(c1) || word => Integer::from(c2 / c1),
+            b'%' => Integer::from(c2 % c1),
+            b'\\' => Integer::from(c2.div_floor(c1)),
+            b'm' => Integer::from(c2.rem_floor(c1)),
+            b'^' if *c1 >= 0 && word => {
+                let modulo = Integer::from(1) << ctx.word_width().bits();
+                c2.clone().pow_mod(c1, &modulo).unwrap_or_default()
+            },
+            b'^' if *c1 >= 0 => return Some(c2.power(c1, ctx, span)),
+            b'&' => Integer::from(c2 & c1),
+            b'|' => Integer::from(c2 | c1),
+            b'x' => Integer::from(c2 ^ c1),
+            b'<' | b'>' if *c1 < 0 => {
+                return Some(Err(CalcError::Domain { name: self.name().to_string(), span: span.clone() }));
+            },
+            b'<' => match c1.to_u32() {
+                Some(s) if word && s >= ctx.word_width().bits() => Integer::new(),
+                Some(s) if word || c2.significant_bits() as u64 + s as u64 <= ctx.max_bits() as u64 => {
+                    Integer::from(c2 << s)
+                },
+                _ if *c2 == 0 => Integer::new(),
+                _ => return Some(Err(CalcError::BeyondAccuracy { span: span.clone() }))
+            },
+            b'>' => match c1.to_u32() {
+                Some(s) if s < c2.significant_bits() => Integer::from(c2 >> s),
+                _ if *c2 < 0 => Integer::from(-1),
+                _ => Integer::new(),
+            },
+            _ => return None
+        };
+        Some(res.accuracy(ctx, span))
+    }
+
+    fn rational(&self, c2: &Rational, c1: &Rational, ctx: &Context, span: &Span) -> Option<Result<Rational, CalcError>> {
+        let res = match self {
+            b'+' => Rational::from(c2 + c1),
+            b'-' => Rational::from(c2 - c1),
+            b'*' => Rational::from(c2 * c1),
+            b'/' | b'%' | b'\\' | b'm' if *c1 == 0 => {
+                return Some(Err(CalcError::DivideByZero { op: self.name().to_string(), span: span.clone() }));
+            },
+            b'/' => Rational::from(c2 / c1),
+            b'\\' => Rational::from(c2 / c1).floor(),
+            b'%' | b'm' => {
+                let q = Rational::from(c2 / c1);
+                let mut q = if *self == b'm' { q.floor() } else { q.trunc() };
+                q *= c1;
+                Rational::from(c2 - &q)
+            },
+            b'^' if *c1.denom() == 1 => {
+                let e = Integer::from(c1.numer().abs_ref());
+                if *c2 == 0 && *c1 < 0 {
+                    return Some(Err(CalcError::DivideByZero { op: self.name().to_string(), span: span.clone() }));
+                }
+                let (n, d) = match (c2.numer().power(&e, ctx, span), c2.denom().power(&e, ctx, span)) {
+                    (Ok(n), Ok(d)) => (n, d),
+                    (Err(err), _) | (_, Err(err)) => return Some(Err(err)),
+                };
+                if *c1 < 0 { Rational::from((d, n)) } else { Rational::from((n, d)) }
+            },
+            _ => return None
+        };
+        Some(res.accuracy(ctx, span))
+    }
 }
 
 impl Bignum for Float {
-    fn fmod(&self, n: &Float) -> Float {
-        let mut m = Float::with_val(2560, self / n);
-        if self < &0.0 {
-            m.ceil_mut()
-        } else { m.floor_mut() };
-        Float::with_val(2560, self - &m * n)
+    fn fmod(&self, n: &Float, floored: bool, ctx: &Context) -> Float {
+        let mut m = Float::with_val(ctx.prec() + 64, self / n);
+        if floored {
+            m.floor_mut()
+        } else { m.trunc_mut() };
+        ctx.float(self - &m * n)
     }
 
-    fn accuracy(self) -> Result<Float, String> {
-        if *MAX > self && *MIN < self {
+    fn accuracy(self, ctx: &Context, span: &Span) -> Result<Float, CalcError> {
+        if *self.as_abs() < *ctx.max() {
             return Ok(self);
         }
-        Err("Beyond Accuracy".to_string())
+        Err(CalcError::BeyondAccuracy { span: span.clone() })
     }
 
-    fn to_string_round(&self, digits: Option<usize>) -> String {
-        let fix = if self < &1.0 && self > &-1.0 && self != &0.0 {
-            self.to_string_radix(10, None).to_fixed()
-        } else { self.to_string_radix(10, None).clean_zero() };
+    fn round_by(&self, ctx: &Context) -> Float {
+        match ctx.rounding_rule() {
+            Rounding::HalfUp => ctx.float(self.round_ref()),
+            Rounding::HalfEven => ctx.float(self.round_even_ref()),
+            Rounding::Zero => ctx.float(self.trunc_ref()),
+        }
+    }
+
+    fn round_digits(&self, digits: i32, ctx: &Context) -> Float {
+        let exact = (ctx.prec() as f64 / LOG2_10).ceil() as i32;
+        if digits >= exact {
+            return self.clone();
+        }
+
+        let scale = ctx.float(Float::u_pow_u(10, digits.unsigned_abs()));
+        if digits >= 0 {
+            let m = ctx.float(self * &scale).round_by(ctx);
+            ctx.float(&m / &scale)
+        } else {
+            let m = ctx.float(self / &scale).round_by(ctx);
+            ctx.float(&m * &scale)
+        }
+    }
+
+    fn lambert_w(&self, branch: i32, ctx: &Context) -> Option<Float> {
+        let prec = ctx.prec() + 64;
+        let bound = -Float::with_val(prec, -1).exp();
+        if *self < bound || branch == -1 && *self >= 0.0 || branch != 0 && branch != -1 {
+            return None;
+        } else if *self == 0.0 {
+            return Some(ctx.float(0));
+        }
+
+        let x = Float::with_val(prec, self);
+        let mut w = if x < -0.25 {
+            let p = (2.0 * (1.0 + std::f64::consts::E * x.to_f64())).max(0.0).sqrt();
+            let p = if branch == 0 { p } else { -p };
+            Float::with_val(prec, -1.0 + p - p * p / 3.0)
+        } else if branch == -1 || x >= 3.0 {
+            let l = Float::with_val(prec, x.as_abs().ln_ref());
+            let ll = Float::with_val(prec, l.as_abs().ln_ref());
+            Float::with_val(prec, &l - &ll)
+        } else {
+            Float::with_val(prec, x.to_f64().ln_1p())
+        };
+
+        for _ in 0..200 {
+            let ew = Float::with_val(prec, w.exp_ref());
+            let f = Float::with_val(prec, &w * &ew) - &x;
+            let w1 = Float::with_val(prec, &w + 1u32);
+            if w1 == 0.0 {
+                break;
+            }
+            let mut t = Float::with_val(prec, &w + 2u32);
+            t *= &f;
+            t /= &w1;
+            t /= 2u32;
+            let mut den = Float::with_val(prec, &ew * &w1);
+            den -= &t;
+            if den == 0.0 {
+                break;
+            }
+            let delta = Float::with_val(prec, &f / &den);
+            w -= &delta;
+            match (delta.get_exp(), w.get_exp()) {
+                (Some(d), Some(e)) if d > e - prec as i32 => {},
+                _ => break,
+            }
+        }
+        Some(ctx.float(&w))
+    }
 
+    fn to_string_round(&self, digits: Option<usize>, radix: i32) -> String {
+        let fix = self.to_string_radix(radix, None).to_fixed(radix);
         match digits {
             None => fix,
-            Some(x) => {
-                if let None = fix.find('.') {
-                    return fix;
-                } else if x < 2 {
-                    return "Set Accuracy Greater Than 1".to_string();
-                }
+            Some(x) => fix.round_fixed(x - 1, radix)
+        }
+    }
+}
 
-                let mut n: usize = 0;
-                let mut dig: usize = 0;
-                let mut point: bool = false;
-                let mut res = String::new();
-
-                for (i, v) in fix.as_bytes().iter().enumerate() {
-                    match v {
-                        b'-' => n = 1,
-                        b'.' => { dig = 0; point = true; },
-                        _ => dig += 1,
-                    }
-                    if dig < x && i == fix.len()-1 {
-                        return fix;
-                    } else if point == true && dig == x && i <= fix.len()-1 {
-                        let a = fix[i..i+1].parse::<u32>().unwrap();
-                        let b = fix[i-1..i].parse::<u32>().unwrap();
-                        res = fix[..i].to_string();
-                        if a < 5 {
-                            return res.clean_zero();
-                        } else if b < 9 {
-                            res.pop();
-                            res.push(from_digit(b+1, 10).unwrap());
-                            return res;
-                        }
-                        break;
-                    }
-                }
+impl Bigcomplex for Complex {
+    fn accuracy(self, ctx: &Context, span: &Span) -> Result<Complex, CalcError> {
+        if *self.real().as_abs() < *ctx.max() && *self.imag().as_abs() < *ctx.max() {
+            return Ok(self);
+        }
+        Err(CalcError::BeyondAccuracy { span: span.clone() })
+    }
 
-                let rev = res.chars().rev().collect::<String>();
-                for (i, v) in rev.as_bytes().iter().enumerate() {
-                    if v == &b'.' {
-                        continue;
-                    }
-                    let a = rev[i..i+1].parse::<u32>().unwrap();
-                    if a == 9 {
-                        res.remove(res.len()-1-i);
-                        if i == rev.len()-1-n {
-                            res.insert_str(0+n, &(a+1).to_string());
-                            return res.clean_zero();
-                        }
-                        res.insert(res.len()-i, from_digit(0, 10).unwrap());
-                    } else if a < 9 {
-                        res.remove(res.len()-1-i);
-                        res.insert(res.len()-i, from_digit(a+1, 10).unwrap());
-                        return res.clean_zero();
-                    }
-                    let point = rev[i+1..i+2].as_bytes();
-                    if point == &[b'.'] {
-                        continue;
-                    }
-                    let b = rev[i+1..i+2].parse::<u32>().unwrap();
-                    if b < 9 {
-                        res.remove(res.len()-2-i);
-                        res.insert(res.len()-1-i, from_digit(b+1, 10).unwrap());
-                        return res.clean_zero();
-                    }
-                }
-                exit(0)
-            }
+    fn to_string_round(&self, digits: Option<usize>, radix: i32) -> String {
+        let re = self.real().to_string_round(digits, radix);
+        let im = self.imag().to_string_round(digits, radix);
+        let (sign, im) = match im.strip_prefix('-') {
+            Some(valid) => ('-', valid.to_string()),
+            None => ('+', im),
+        };
+        let unit = match im.as_str() {
+            "0" => return re,
+            "1" => "i".to_string(),
+            _ => im + "i",
+        };
+        match (re.as_str(), sign) {
+            ("0", '-') => format!("-{}", unit),
+            ("0", _) => unit,
+            _ => format!("{} {} {}", re, sign, unit),
         }
     }
+
+    fn to_polar_round(&self, digits: Option<usize>, ctx: &Context) -> String {
+        let r = ctx.float(self.abs_ref());
+        let theta = ctx.arc(self.arg_ref());
+        let radix = ctx.output_radix();
+        format!("{}∠{}", r.to_string_round(digits, radix), theta.to_string_round(digits, radix))
+    }
 }
 
-impl Other for String {
-    fn to_fixed(&self) -> String {
-        let mut exp: i32 = 0;
-        let (mut zero, mut i_or_u) = (0, 0);
-        let (mut temp, mut res) = (String::new(), String::new());
-
-        for (i, v) in self.as_bytes().iter().enumerate() {
-            if v == &b'e' {
-                temp = self[..i].to_string();
-                exp = self[i+1..].parse::<i32>().unwrap();
-                break;
+impl Bigint for Integer {
+    fn accuracy(self, ctx: &Context, span: &Span) -> Result<Integer, CalcError> {
+        if self.significant_bits() <= ctx.max_bits() {
+            return Ok(self);
+        }
+        Err(CalcError::BeyondAccuracy { span: span.clone() })
+    }
+
+    fn power(&self, e: &Integer, ctx: &Context, span: &Span) -> Result<Integer, CalcError> {
+        if self.significant_bits() <= 1 {
+            let e = if *e == 0 { 0 } else if e.is_even() { 2 } else { 1 };
+            return Ok(Integer::from(self.pow(e)));
+        }
+        match e.to_u32() {
+            Some(n) if self.significant_bits() as u64 * n as u64 <= ctx.max_bits() as u64 => {
+                Integer::from(self.pow(n)).accuracy(ctx, span)
+            },
+            _ => Err(CalcError::BeyondAccuracy { span: span.clone() })
+        }
+    }
+
+    fn factor(&self, span: &Span) -> Result<Vec<(Integer, u32)>, CalcError> {
+        if *self == 0 {
+            return Err(CalcError::Domain { name: "factor".to_string(), span: span.clone() });
+        } else if self.significant_bits() > PRIME_BITS {
+            return Err(CalcError::BeyondAccuracy { span: span.clone() });
+        }
+        let mut primes = if *self < 0 { vec![Integer::from(-1)] } else { Vec::new() };
+        let mut n = Integer::from(self.abs_ref());
+        let mut d = 2u32;
+        while d < 10000 && Integer::from(d) * d <= n {
+            while n.is_divisible_u(d) {
+                primes.push(Integer::from(d));
+                n /= d;
             }
+            d += if d == 2 { 1 } else { 2 };
+        }
+        if n.significant_bits() > FACTOR_BITS {
+            return Err(CalcError::BeyondAccuracy { span: span.clone() });
         }
 
-        if exp == 0 { temp = self.clone() };
-        for (i, v) in temp.as_bytes().iter().enumerate() {
-            match v {
-                b'.' => {
-                    res = temp[..i].to_string();
-                    res += &temp[i+1..]; zero = 0;
-                },
-                b'-' => {
-                    if exp < 0 {
-                        i_or_u = 1
-                    } else { exp += 1 }; zero = 0;
+        let mut rest = vec![n];
+        while let Some(m) = rest.pop() {
+            if m == 1 {
+                continue;
+            } else if m.is_probably_prime(30) != IsPrime::No {
+                primes.push(m);
+                continue;
+            }
+            match m.rho() {
+                Some(f) => {
+                    rest.push(Integer::from(&m / &f));
+                    rest.push(f);
                 },
-                b'0' => zero += 1,
-                _ => zero = 0,
+                None => return Err(CalcError::BeyondAccuracy { span: span.clone() })
             }
         }
 
-        if exp < 0 {
-            exp = exp.abs();
-            for _ in 0..exp {
-                res.insert(i_or_u, '0');
-                exp -= 1;
+        primes.sort();
+        let mut res: Vec<(Integer, u32)> = Vec::new();
+        for p in primes {
+            match res.last_mut() {
+                Some((q, k)) if *q == p => *k += 1,
+                _ => res.push((p, 1)),
             }
-            if i_or_u != 0 {
-                exp += 1;
+        }
+        Ok(res)
+    }
+
+    fn rho(&self) -> Option<Integer> {
+        let step = |v: &Integer, c: u32| {
+            let mut v = Integer::from(v.square_ref());
+            v += c;
+            v % self
+        };
+        for c in 1..4 {
+            let (mut x, mut y) = (Integer::from(2), Integer::from(2));
+            for _ in 0..1 << 18 {
+                x = step(&x, c);
+                y = step(&step(&y, c), c);
+                let d = Integer::from(&x - &y).abs().gcd(self);
+                if d == *self {
+                    break;
+                } else if d != 1 {
+                    return Some(d);
+                }
             }
         }
+        None
+    }
+}
 
-        if exp == 0 && res.len()-zero == 1 {
-            return res[..res.len()-zero].to_string();
-        } else if exp == 0 && res.len()-zero > 1 {
-            res = res[..res.len()-zero].to_string();
-            res.insert(1, '.');
-            return res;
+impl Bigrational for Rational {
+    fn accuracy(self, ctx: &Context, span: &Span) -> Result<Rational, CalcError> {
+        if self.numer().significant_bits() <= ctx.max_bits() && self.denom().significant_bits() <= ctx.max_bits() {
+            return Ok(self);
         }
+        Err(CalcError::BeyondAccuracy { span: span.clone() })
+    }
 
-        let u_exp = exp as usize + 1;
-        res.insert(u_exp, '.');
-        if u_exp >= res.len()-1-zero {
-            return res[..u_exp].to_string();
+    fn round_by(&self, ctx: &Context) -> Rational {
+        match ctx.rounding_rule() {
+            Rounding::HalfUp => self.clone().round(),
+            Rounding::Zero => self.clone().trunc(),
+            Rounding::HalfEven => {
+                let (frac, int) = self.clone().fract_floor(Integer::new());
+                let up = match frac.cmp(&Rational::from((1, 2))) {
+                    Ordering::Less => false,
+                    Ordering::Greater => true,
+                    Ordering::Equal => int.is_odd(),
+                };
+                Rational::from(if up { int + 1 } else { int })
+            },
         }
-        res[..res.len()-zero].to_string()
+    }
+
+    fn round_digits(&self, digits: i32, ctx: &Context) -> Rational {
+        let scale = Rational::from(Integer::from(Integer::u_pow_u(10, digits.unsigned_abs())));
+        if digits >= 0 {
+            let m = Rational::from(self * &scale).round_by(ctx);
+            Rational::from(&m / &scale)
+        } else {
+            let m = Rational::from(self / &scale).round_by(ctx);
+            Rational::from(&m * &scale)
+        }
+    }
+
+    fn to_string_round(&self, digits: Option<usize>, ctx: &Context) -> String {
+        let radix = ctx.output_radix();
+        if *self.denom() == 1 {
+            return self.numer().to_string_radix(radix);
+        }
+        match ctx.fraction_style() {
+            Fraction::Improper => self.to_string_radix(radix),
+            Fraction::Decimal => ctx.float(self).to_string_round(digits, radix),
+            Fraction::Mixed => {
+                let (frac, int) = self.clone().fract_trunc(Integer::new());
+                if int == 0 {
+                    return frac.to_string_radix(radix);
+                }
+                format!("{} {}", int.to_string_radix(radix), Rational::from(frac.abs_ref()).to_string_radix(radix))
+            },
+        }
+    }
+}
+
+impl Other for String {
+    fn to_fixed(&self, radix: i32) -> String {
+        if !self.starts_with(|c: char| c == '-' || c.is_ascii_alphanumeric())
+            || self.ends_with("inf") || self.ends_with("NaN") || self.ends_with('@') {
+            return self.clone();
+        }
+
+        let (temp, exp) = match self.find(if radix > 10 { '@' } else { 'e' }) {
+            Some(i) => (&self[..i], self[i+1..].parse::<i64>().unwrap_or(0)),
+            None => (&self[..], 0),
+        };
+        let (sign, temp) = match temp.strip_prefix('-') {
+            Some(valid) => ("-", valid),
+            None => ("", temp),
+        };
+
+        let digits = temp.replace('.', "");
+        let point = temp.find('.').unwrap_or(temp.len()) as i64 + exp;
+        let (int, frac) = if point <= 0 {
+            (String::new(), "0".repeat(point.unsigned_abs() as usize) + &digits)
+        } else if point as usize >= digits.len() {
+            (digits.clone() + &"0".repeat(point as usize - digits.len()), String::new())
+        } else {
+            (digits[..point as usize].to_string(), digits[point as usize..].to_string())
+        };
+
+        let int = match int.trim_start_matches('0') {
+            "" => "0",
+            valid => valid,
+        };
+        let res = format!("{}.{}", int, frac).clean_zero();
+        if res == "0" { res } else { sign.to_string() + &res }
+    }
+
+    fn round_fixed(self, places: usize, radix: i32) -> String {
+        let point = match self.find('.') {
+            Some(i) if self.len() - i - 1 > places => i,
+            _ => return self,
+        };
+
+        let bytes = self.as_bytes();
+        let mut res = bytes[..point+1+places].to_vec();
+        let (digit, radix) = (|b: u8| (b as char).to_digit(36).unwrap_or(0), radix as u32);
+        if digit(bytes[point+1+places]) * 2 >= radix {
+            let mut i = res.len();
+            let mut carry = true;
+            while i > 0 && res[i-1] != b'-' {
+                i -= 1;
+                match res[i] {
+                    b'.' => {},
+                    ch if digit(ch) + 1 == radix => res[i] = b'0',
+                    ch => {
+                        res[i] = std::char::from_digit(digit(ch) + 1, radix).map_or(ch, |c| c as u8);
+                        carry = false;
+                        break;
+                    },
+                }
+            }
+            if carry {
+                res.insert(i, b'1');
+            }
+        }
+        res.into_iter().map(char::from).collect::<String>().clean_zero()
     }
 
     fn clean_zero(self) -> String {
@@ -245,218 +578,421 @@ impl Other for String {
         self
     }
 
-    fn math(&self, v: Float) -> Result<Float, String> {
+    fn math(&self, v: Float, ctx: &Context, span: &Span) -> Result<Float, CalcError> {
+        let recip = || Float::with_val(ctx.prec() + 64, v.recip_ref());
         match self.as_str() {
-            "abs" => v.abs().accuracy(),
-            "ln" if v > 0.0 => v.ln().accuracy(),
-            "exp" => v.exp().accuracy(),
-            "log" if v > 0.0 => v.log2().accuracy(),
-            "logx" if v > 0.0 => v.log10().accuracy(),
-            "cos" => v.cos().accuracy(),
-            "sin" => v.sin().accuracy(),
-            "tan" => v.tan().accuracy(),
-            "csc" if v != 0.0 => v.csc().accuracy(),
-            "sec" => v.sec().accuracy(),
-            "cot" if v != 0.0 => v.cot().accuracy(),
-            "cosh" => v.cosh().accuracy(),
-            "sinh" => v.sinh().accuracy(),
-            "tanh" => v.tanh().accuracy(),
-            "csch" if v != 0.0 => v.csch().accuracy(),
-            "sech" => v.sech().accuracy(),
-            "coth" if v != 0.0 => v.coth().accuracy(),
-            "acos" if v >= -1.0 && v <= 1.0 => v.acos().accuracy(),
-            "asin" if v >= -1.0 && v <= 1.0 => v.asin().accuracy(),
-            "atan" => v.atan().accuracy(),
-            "acosh" if v >= 1.0 => v.acosh().accuracy(),
-            "asinh" => v.asinh().accuracy(),
-            "atanh" if v > -1.0 && v < 1.0 => v.atanh().accuracy(),
-            "cbrt" => v.cbrt().accuracy(),
-            "sqrt" if v >= 0.0 => v.sqrt().accuracy(),
+            "abs" => ctx.float(v.abs_ref()).accuracy(ctx, span),
+            "ln" if v > 0.0 => ctx.float(v.ln_ref()).accuracy(ctx, span),
+            "exp" => ctx.float(v.exp_ref()).accuracy(ctx, span),
+            "log2" if v > 0.0 => ctx.float(v.log2_ref()).accuracy(ctx, span),
+            "log10" if v > 0.0 => ctx.float(v.log10_ref()).accuracy(ctx, span),
+            "cos" | "sin" | "tan" | "csc" | "sec" | "cot" => self.trig(v, ctx, span),
+            "cosh" => ctx.float(v.cosh_ref()).accuracy(ctx, span),
+            "sinh" => ctx.float(v.sinh_ref()).accuracy(ctx, span),
+            "tanh" => ctx.float(v.tanh_ref()).accuracy(ctx, span),
+            "csch" if v != 0.0 => ctx.float(v.csch_ref()).accuracy(ctx, span),
+            "sech" => ctx.float(v.sech_ref()).accuracy(ctx, span),
+            "coth" if v != 0.0 => ctx.float(v.coth_ref()).accuracy(ctx, span),
+            "acos" if (-1.0..=1.0).contains(&v) => ctx.arc(v.acos_ref()).accuracy(ctx, span),
+            "asin" if (-1.0..=1.0).contains(&v) => ctx.arc(v.asin_ref()).accuracy(ctx, span),
+            "atan" => ctx.arc(v.atan_ref()).accuracy(ctx, span),
+            "acosh" if v >= 1.0 => ctx.float(v.acosh_ref()).accuracy(ctx, span),
+            "asinh" => ctx.float(v.asinh_ref()).accuracy(ctx, span),
+            "atanh" if v > -1.0 && v < 1.0 => ctx.float(v.atanh_ref()).accuracy(ctx, span),
+            "acsc" if v <= -1.0 || v >= 1.0 => ctx.arc(recip().asin_ref()).accuracy(ctx, span),
+            "asec" if v <= -1.0 || v >= 1.0 => ctx.arc(recip().acos_ref()).accuracy(ctx, span),
+            "acot" if v == 0.0 => {
+                let pi = Float::with_val(ctx.prec() + 64, Constant::Pi);
+                ctx.arc(pi / 2u32).accuracy(ctx, span)
+            },
+            "acot" => ctx.arc(recip().atan_ref()).accuracy(ctx, span),
+            "acsch" if v != 0.0 => ctx.float(recip().asinh_ref()).accuracy(ctx, span),
+            "asech" if v > 0.0 && v <= 1.0 => ctx.float(recip().acosh_ref()).accuracy(ctx, span),
+            "acoth" if !(-1.0..=1.0).contains(&v) => ctx.float(recip().atanh_ref()).accuracy(ctx, span),
+            "versin" | "haversine" => {
+                let (x, _) = ctx.radians(&v);
+                let half = Float::with_val(ctx.prec() + 64, &x / 2u32);
+                let mut res = Float::with_val(ctx.prec() + 64, half.sin_ref());
+                res.square_mut();
+                if self == "versin" {
+                    res *= 2u32;
+                }
+                ctx.float(&res).accuracy(ctx, span)
+            },
+            "cbrt" => ctx.float(v.cbrt_ref()).accuracy(ctx, span),
+            "sqrt" if v >= 0.0 => ctx.float(v.sqrt_ref()).accuracy(ctx, span),
+            "floor" => ctx.float(v.floor_ref()).accuracy(ctx, span),
+            "ceil" => ctx.float(v.ceil_ref()).accuracy(ctx, span),
+            "trunc" => ctx.float(v.trunc_ref()).accuracy(ctx, span),
+            "frac" => ctx.float(v.fract_ref()).accuracy(ctx, span),
+            "sign" if v == 0.0 => Ok(ctx.float(0)),
+            "sign" => ctx.float(v.signum_ref()).accuracy(ctx, span),
+            "deg" => ctx.float(&ctx.convert(&v, Angle::Radian, Angle::Degree)).accuracy(ctx, span),
+            "rad" => ctx.float(&ctx.convert(&v, Angle::Degree, Angle::Radian)).accuracy(ctx, span),
+            "gamma" | "lgamma" | "digamma" if v.is_integer() && v <= 0.0 => {
+                Err(CalcError::Domain { name: self.clone(), span: span.clone() })
+            },
+            "erf" => ctx.float(v.erf_ref()).accuracy(ctx, span),
+            "erfc" => ctx.float(v.erfc_ref()).accuracy(ctx, span),
+            "zeta" if v != 1.0 => ctx.float(v.zeta_ref()).accuracy(ctx, span),
+            "j0" => ctx.float(v.j0_ref()).accuracy(ctx, span),
+            "j1" => ctx.float(v.j1_ref()).accuracy(ctx, span),
+            "y0" if v > 0.0 => ctx.float(v.y0_ref()).accuracy(ctx, span),
+            "y1" if v > 0.0 => ctx.float(v.y1_ref()).accuracy(ctx, span),
+            "eint" if v != 0.0 => ctx.float(v.eint_ref()).accuracy(ctx, span),
+            "li2" if v <= 1.0 => ctx.float(v.li2_ref()).accuracy(ctx, span),
+            "fac" if v.is_integer() && v < 0.0 => Err(CalcError::Domain { name: self.clone(), span: span.clone() }),
+            "gamma" => ctx.float(v.gamma_ref()).accuracy(ctx, span),
+            "lgamma" => ctx.float(&v.ln_abs_gamma().0).accuracy(ctx, span),
+            "digamma" => ctx.float(v.digamma_ref()).accuracy(ctx, span),
+            "fac" if v > ctx.max_exp().max(25) => Err(CalcError::BeyondAccuracy { span: span.clone() }),
+            "fac" if !v.is_integer() => {
+                let x = Float::with_val(ctx.prec() + 64, &v + 1u32);
+                ctx.float(x.gamma_ref()).accuracy(ctx, span)
+            },
             "fac" => {
-                let to_u32 = v.to_u32_saturating().unwrap();
-                let fac = Float::factorial(to_u32);
-                Float::with_val(2560, fac).accuracy()
+                let to_u32 = v.to_u32_saturating().unwrap_or(0);
+                ctx.float(Float::factorial(to_u32)).accuracy(ctx, span)
+            },
+            "dfac" if !v.is_integer() || v < -1.0 => Err(CalcError::Domain { name: self.clone(), span: span.clone() }),
+            "dfac" if v > ctx.max_exp().max(25).saturating_mul(2) => Err(CalcError::BeyondAccuracy { span: span.clone() }),
+            "dfac" => {
+                let to_u32 = v.to_u32_saturating().unwrap_or(0);
+                ctx.float(&Integer::from(Integer::factorial_2(to_u32))).accuracy(ctx, span)
             },
-            _ => Err("Parameter Error".to_string())
+            _ => Err(CalcError::Domain { name: self.clone(), span: span.clone() })
         }
     }
 
-    fn extract(&self, n: usize, i: usize) -> Result<Float, String> {
-        match Float::parse(&self[n..i]) {
-            Ok(valid) => Float::with_val(2560, valid).accuracy(),
-            Err(_) => Err("Invalid Number".to_string())
+    fn trig(&self, v: Float, ctx: &Context, span: &Span) -> Result<Float, CalcError> {
+        let (x, quarter) = ctx.radians(&v);
+        if let Some(k) = quarter {
+            let exact = match self.as_str() {
+                "sin" => [Some(0), Some(1), Some(0), Some(-1)],
+                "cos" => [Some(1), Some(0), Some(-1), Some(0)],
+                "tan" => [Some(0), None, Some(0), None],
+                "csc" => [None, Some(1), None, Some(-1)],
+                "sec" => [Some(1), None, Some(-1), None],
+                _ => [None, Some(0), None, Some(0)],
+            };
+            return match exact[k] {
+                Some(n) => Ok(ctx.float(n)),
+                None => Err(CalcError::Domain { name: self.clone(), span: span.clone() })
+            };
         }
+
+        let res = match self.as_str() {
+            "cos" => ctx.float(x.cos_ref()),
+            "sin" => ctx.float(x.sin_ref()),
+            "tan" => ctx.float(x.tan_ref()),
+            "csc" => ctx.float(x.csc_ref()),
+            "sec" => ctx.float(x.sec_ref()),
+            _ => ctx.float(x.cot_ref()),
+        };
+        res.accuracy(ctx, span)
     }
-}
 
-impl Calc {
-    pub fn new(expr: String) -> Self {
-        Self {
-            sign: RefCell::new(Sign::Init),
-            numbers: RefCell::new(Vec::new()),
-            operator: RefCell::new(Vec::new()),
-            func: RefCell::new(HashMap::new()),
-            expression: expr + "=",
-        }
-    }
-
-    pub fn run(&self) -> Result<Float, String> {
-        let num = &self.numbers;
-        let ope = &self.operator;
-        let expr = &self.expression;
-        let math = ["abs","cos","sin","tan","csc","sec","cot","coth",
-            "cosh","sinh","tanh","sech","ln","csch","acos","asin","atan",
-            "acosh","asinh","atanh","exp","log","logx","sqrt","cbrt","fac"];
-        let mut mark: u8 = b'I'; // I = Init, C = Char, N = Number, F = Func, P = Pi
-        let mut locat: usize = 0;
-        let mut bracket: u32 = 0;
-
-        for (index, &valid) in expr.as_bytes().iter().enumerate() {
-            match valid {
-                b'0'..=b'9' | b'.' => {
-                    if mark != b')' && mark != b'P' && mark != b'F' {
-                        mark = b'N';
-                        continue;
-                    }
-                    return Err("Expression Error".to_string());
+    fn call(&self, args: Vec<Float>, ctx: &Context, span: &Span) -> Result<Float, CalcError> {
+        match (self.as_str(), args.as_slice()) {
+            ("atan2", [y, x]) => ctx.arc(y.atan2_ref(x)).accuracy(ctx, span),
+            ("hypot", [a, b]) => ctx.float(a.hypot_ref(b)).accuracy(ctx, span),
+            ("log", [x, b]) if *x > 0.0 && *b > 0.0 && *b != 1.0 => {
+                let (n, m) = (ctx.float(x.ln_ref()), ctx.float(b.ln_ref()));
+                ctx.float(&n / &m).accuracy(ctx, span)
+            },
+            ("root", [x, n]) => match n.to_u32_saturating() {
+                Some(k) if k > 0 && *n == k && (*x >= 0.0 || k % 2 == 1) => {
+                    ctx.float(x.root_ref(k)).accuracy(ctx, span)
+                },
+                _ => Err(CalcError::Domain { name: self.clone(), span: span.clone() })
+            },
+            ("beta", [a, b]) => {
+                let pole = |v: &Float| v.is_integer() && *v <= 0.0;
+                let prec = ctx.prec() + 64;
+                let sum = Float::with_val(prec, a + b);
+                if pole(a) || pole(b) {
+                    return Err(CalcError::Domain { name: self.clone(), span: span.clone() });
+                } else if pole(&sum) {
+                    return Ok(ctx.float(0));
                 }
-
-                b'a'..=b'z' => {
-                    if mark != b')' && mark != b'P' && mark != b'-' && mark != b'N' {
-                        mark = b'F';
-                        continue;
-                    }
-                    return Err("Expression Error".to_string());
+                let (la, sa) = Float::with_val(prec, a).ln_abs_gamma();
+                let (lb, sb) = Float::with_val(prec, b).ln_abs_gamma();
+                let (ls, ss) = sum.ln_abs_gamma();
+                let mut res = Float::with_val(prec, &la + &lb);
+                res -= &ls;
+                res.exp_mut();
+                if (sa == Ordering::Less) ^ (sb == Ordering::Less) ^ (ss == Ordering::Less) {
+                    res = -res;
                 }
-
-                ch @ b'+' | ch @ b'-' | ch @ b'*' | ch @ b'/' | ch @ b'%' | ch @ b'^' => {
-                    if ch == b'-' && ( mark == b'I' || mark == b'(' || mark == b'C' ) {
-                        mark = b'-';
-                        continue;
-                    } else if mark != b'N' && mark != b')' && mark != b'P' {
-                        return Err("Expression Error".to_string());
-                    }
-
-                    if let Sign::Char | Sign::Init = self.sign.clone().into_inner() {
-                        num.borrow_mut().push(expr.extract(locat, index)?);
-                        *self.sign.borrow_mut() = Sign::Data;
-                    }
-
-                    while ope.borrow().len() != 0 && ope.borrow().last().unwrap() != &b'(' {
-                        if ope.borrow().last().unwrap().priority() >= ch.priority() {
-                            let value = ope.borrow_mut().pop().unwrap().computing(self)?;
-                            num.borrow_mut().push(value);
-                        } else {
-                            break;
-                        }
-                    }
-
-                    ope.borrow_mut().push(ch);
-                    *self.sign.borrow_mut() = Sign::Char;
-                    locat = index + 1;
-                    mark = b'C';
-                    continue;
+                ctx.float(&res).accuracy(ctx, span)
+            },
+            ("jn", [n, x]) | ("yn", [n, x]) if n.is_integer() && (*x > 0.0 || self == "jn") => {
+                let k = n.to_i32_saturating().unwrap_or(0);
+                match self.as_str() {
+                    "jn" => ctx.float(x.jn_ref(k)).accuracy(ctx, span),
+                    _ => ctx.float(x.yn_ref(k)).accuracy(ctx, span),
                 }
-
-                ch @ b'(' => {
-                    if mark == b'F' {
-                        let valid = expr[locat..index].to_string();
-                        if math.iter().any(|&value| value == valid) {
-                            self.func.borrow_mut().insert(bracket+1, valid);
-                        } else {
-                            return Err("Function Undefined".to_string());
-                        }
-                    }
-
-                    if let Sign::Char | Sign::Init = self.sign.clone().into_inner() {
-                        if mark != b'N' && mark != b'-' {
-                            ope.borrow_mut().push(ch);
-                            locat = index + 1;
-                            bracket = bracket + 1;
-                            mark = b'(';
-                            continue;
-                        }
-                    }
-                    return Err("Expression Error".to_string());
+            },
+            ("agm", [a, b]) if *a >= 0.0 && *b >= 0.0 => ctx.float(a.agm_ref(b)).accuracy(ctx, span),
+            ("lambertw", [x]) | ("lambertw", [x, _]) => {
+                let branch = match args.get(1) {
+                    Some(k) if k.is_integer() => k.to_i32_saturating().unwrap_or(1),
+                    Some(_) => 1,
+                    None => 0,
+                };
+                match x.lambert_w(branch, ctx) {
+                    Some(valid) => valid.accuracy(ctx, span),
+                    None => Err(CalcError::Domain { name: self.clone(), span: span.clone() })
                 }
+            },
+            ("round", [x]) => x.round_by(ctx).accuracy(ctx, span),
+            ("round", [x, d]) if d.is_integer() => match d.to_i32_saturating() {
+                Some(k) if k.unsigned_abs() <= ctx.max_exp() => x.round_digits(k, ctx).accuracy(ctx, span),
+                _ => Err(CalcError::BeyondAccuracy { span: span.clone() })
+            },
+            ("min", [first, rest @ ..]) => {
+                Ok(rest.iter().fold(first, |a, b| if b < a { b } else { a }).clone())
+            },
+            ("max", [first, rest @ ..]) => {
+                Ok(rest.iter().fold(first, |a, b| if b > a { b } else { a }).clone())
+            },
+            (_, [v]) => self.math(v.clone(), ctx, span),
+            _ => Err(CalcError::Domain { name: self.clone(), span: span.clone() })
+        }
+    }
 
-                b')' => {
-                    if let Sign::Char | Sign::Init = self.sign.clone().into_inner() {
-                        if mark == b'N' {
-                            num.borrow_mut().push(expr.extract(locat, index)?);
-                            *self.sign.borrow_mut() = Sign::Data;
-                        }
-                    }
-
-                    if let Sign::Data = self.sign.clone().into_inner() {
-                        if bracket > 0 {
-                            while ope.borrow().last().unwrap() != &b'(' {
-                                let value = ope.borrow_mut().pop().unwrap().computing(self)?;
-                                num.borrow_mut().push(value);
-                            }
-
-                            if let Some(fun) = self.func.borrow_mut().remove(&bracket) {
-                                let value = fun.math(num.borrow_mut().pop().unwrap())?;
-                                num.borrow_mut().push(value);
-                            }
-
-                            ope.borrow_mut().pop();
-                            locat = index + 1;
-                            bracket = bracket - 1;
-                            mark = b')';
-                            continue;
-                        }
-                    }
-                    return Err("Expression Error".to_string());
-                }
+    fn math_complex(&self, v: Complex, ctx: &Context, span: &Span) -> Result<Complex, CalcError> {
+        let zero = |z: &Complex| z.real().is_zero() && z.imag().is_zero();
+        let domain = || CalcError::Domain { name: self.clone(), span: span.clone() };
+        let recip = |z: Complex| if zero(&z) { Err(domain()) } else { Ok(ctx.complex(&ctx.complex((1, 0)) / &z)) };
+        let x = || ctx.complex(&ctx.scale(&v, ctx.angle_unit(), Angle::Radian));
+        let res = match self.as_str() {
+            "abs" => ctx.complex((ctx.float(v.abs_ref()), 0)),
+            "ln" | "log2" | "log10" | "cbrt" if zero(&v) => return Err(domain()),
+            "ln" => ctx.complex(v.ln_ref()),
+            "log2" => {
+                let (n, m) = (ctx.complex(v.ln_ref()), ctx.float(Constant::Log2));
+                ctx.complex(&n / &m)
+            },
+            "log10" => ctx.complex(v.log10_ref()),
+            "exp" => ctx.complex(v.exp_ref()),
+            "sqrt" => ctx.complex(v.sqrt_ref()),
+            "cbrt" => {
+                let third = ctx.complex(&ctx.complex(v.ln_ref()) / 3u32);
+                ctx.complex(third.exp_ref())
+            },
+            "cos" => ctx.complex(x().cos_ref()),
+            "sin" => ctx.complex(x().sin_ref()),
+            "tan" => ctx.complex(x().tan_ref()),
+            "csc" => recip(ctx.complex(x().sin_ref()))?,
+            "sec" => recip(ctx.complex(x().cos_ref()))?,
+            "cot" => recip(ctx.complex(x().tan_ref()))?,
+            "cosh" => ctx.complex(v.cosh_ref()),
+            "sinh" => ctx.complex(v.sinh_ref()),
+            "tanh" => ctx.complex(v.tanh_ref()),
+            "csch" => recip(ctx.complex(v.sinh_ref()))?,
+            "sech" => recip(ctx.complex(v.cosh_ref()))?,
+            "coth" => recip(ctx.complex(v.tanh_ref()))?,
+            "acos" => ctx.arc_complex(v.acos_ref()),
+            "asin" => ctx.arc_complex(v.asin_ref()),
+            "atan" => ctx.arc_complex(v.atan_ref()),
+            "acosh" => ctx.complex(v.acosh_ref()),
+            "asinh" => ctx.complex(v.asinh_ref()),
+            "atanh" => ctx.complex(v.atanh_ref()),
+            "acsc" => ctx.arc_complex(recip(v.clone())?.asin_ref()),
+            "asec" => ctx.arc_complex(recip(v.clone())?.acos_ref()),
+            "acot" => ctx.arc_complex(recip(v.clone())?.atan_ref()),
+            "acsch" => ctx.complex(recip(v.clone())?.asinh_ref()),
+            "asech" => ctx.complex(recip(v.clone())?.acosh_ref()),
+            "acoth" => ctx.complex(recip(v.clone())?.atanh_ref()),
+            "deg" => ctx.complex(&ctx.scale(&v, Angle::Radian, Angle::Degree)),
+            "rad" => ctx.complex(&ctx.scale(&v, Angle::Degree, Angle::Radian)),
+            _ => return Err(domain())
+        };
+        res.accuracy(ctx, span)
+    }
 
-                b'=' | b'\n' | b'\r' => {
-                    if mark == b'I' {
-                        return Err("Empty Expression".to_string());
-                    } else if bracket > 0 || mark == b'-' || mark == b'C' || mark == b'F' {
-                        return Err("Expression Error".to_string());
-                    }
-
-                    if let Sign::Char | Sign::Init = self.sign.clone().into_inner() {
-                        num.borrow_mut().push(expr.extract(locat, index)?);
-                        *self.sign.borrow_mut() = Sign::Data;
-                    }
-
-                    while ope.borrow().len() != 0 {
-                        let value = ope.borrow_mut().pop().unwrap().computing(self)?;
-                        num.borrow_mut().push(value);
-                    }
-                    return Ok(num.borrow_mut().pop().unwrap());
+    fn call_complex(&self, args: Vec<Complex>, ctx: &Context, span: &Span) -> Result<Complex, CalcError> {
+        match (self.as_str(), args.as_slice()) {
+            ("log", [x, b]) => {
+                let (n, m) = (ctx.complex(x.ln_ref()), ctx.complex(b.ln_ref()));
+                match (n.real().is_finite() && m.real().is_finite(), m.real().is_zero() && m.imag().is_zero()) {
+                    (true, false) => ctx.complex(&n / &m).accuracy(ctx, span),
+                    _ => Err(CalcError::Domain { name: self.clone(), span: span.clone() })
                 }
+            },
+            (_, [v]) => self.math_complex(v.clone(), ctx, span),
+            _ => Err(CalcError::Domain { name: self.clone(), span: span.clone() })
+        }
+    }
 
-                b'P' => {
-                    if let Sign::Char | Sign::Init = self.sign.clone().into_inner() {
-                        if mark != b'N' && mark != b'F' {
-                            let value = if mark == b'-' {
-                                0.0 - Float::with_val(128, &Constant::Pi)
-                            } else {
-                                Float::with_val(128, &Constant::Pi)
-                            };
-                            num.borrow_mut().push(value);
-                            *self.sign.borrow_mut() = Sign::Data;
-                            locat = index + 1;
-                            mark = b'P';
-                            continue;
-                        }
-                    }
-                    return Err("Expression Error".to_string());
+    fn call_integer(&self, args: &[Integer], ctx: &Context, span: &Span) -> Option<Result<Integer, CalcError>> {
+        let res = match (self.as_str(), args) {
+            ("abs", [n]) => Integer::from(n.abs_ref()),
+            ("round", [n]) | ("floor", [n]) | ("ceil", [n]) | ("trunc", [n]) => n.clone(),
+            ("round", [n, d]) => match d.to_i32() {
+                Some(k) if k.unsigned_abs() > ctx.max_exp() => return Some(Err(CalcError::BeyondAccuracy { span: span.clone() })),
+                Some(k) if k < 0 => Rational::from(n).round_digits(k, ctx).into_numer_denom().0,
+                Some(_) => n.clone(),
+                None => return Some(Err(CalcError::BeyondAccuracy { span: span.clone() }))
+            },
+            ("frac", [_]) => Integer::new(),
+            ("sign", [n]) => Integer::from(n.cmp0() as i32),
+            ("sqrt", [n]) if *n >= 0 && n.is_perfect_square() => Integer::from(n.sqrt_ref()),
+            ("fac", [n]) if *n < 0 => return Some(Err(CalcError::Domain { name: self.clone(), span: span.clone() })),
+            ("fac", [n]) => match n.to_u32() {
+                Some(k) if k as f64 * (k as f64).log2() <= ctx.max_bits() as f64 => Integer::from(Integer::factorial(k)),
+                _ => return Some(Err(CalcError::BeyondAccuracy { span: span.clone() }))
+            },
+            ("dfac", [n]) if *n < -1 => return Some(Err(CalcError::Domain { name: self.clone(), span: span.clone() })),
+            ("dfac", [n]) => match n.to_u32() {
+                Some(k) if k as f64 / 2.0 * (k as f64).log2() <= ctx.max_bits() as f64 => {
+                    Integer::from(Integer::factorial_2(k))
+                },
+                None if *n < 0 => Integer::from(1),
+                _ => return Some(Err(CalcError::BeyondAccuracy { span: span.clone() }))
+            },
+            ("min", [first, rest @ ..]) => rest.iter().fold(first, |a, b| if b < a { b } else { a }).clone(),
+            ("max", [first, rest @ ..]) => rest.iter().fold(first, |a, b| if b > a { b } else { a }).clone(),
+            ("gcd", [first, rest @ ..]) => rest.iter().fold(first.clone(), |a, b| a.gcd(b)),
+            ("lcm", [first, rest @ ..]) => rest.iter().fold(first.clone(), |a, b| a.lcm(b)),
+            ("nCr", [_, r]) | ("nPr", [_, r]) if *r < 0 => return Some(Err(CalcError::Domain { name: self.clone(), span: span.clone() })),
+            ("nCr", [n, r]) | ("nPr", [n, r]) if *n >= 0 && r > n => Integer::new(),
+            ("nCr", [n, r]) | ("nPr", [n, r]) => {
+                let m = if self == "nCr" && *n >= 0 { Integer::from(n - r).min(r.clone()) } else { r.clone() };
+                match (r.to_u32(), m.to_u32()) {
+                    (Some(k), Some(m)) if m as f64 * (n.significant_bits() as f64 + 1.0) <= ctx.max_bits() as f64 => {
+                        let res = Integer::from(n.binomial_ref(k));
+                        if self == "nCr" { res } else { res * Integer::from(Integer::factorial(k)) }
+                    },
+                    _ => return Some(Err(CalcError::BeyondAccuracy { span: span.clone() }))
                 }
+            },
+            ("isprime", [n]) | ("nextprime", [n]) if n.significant_bits() > PRIME_BITS => {
+                return Some(Err(CalcError::BeyondAccuracy { span: span.clone() }));
+            },
+            ("isprime", [n]) => Integer::from((*n > 1 && n.is_probably_prime(30) != IsPrime::No) as u32),
+            ("nextprime", [n]) => Integer::from(n.next_prime_ref()),
+            ("modinv", [a, m]) if *m != 0 => match a.invert_ref(m) {
+                Some(res) => Integer::from(res),
+                None => return Some(Err(CalcError::Domain { name: self.clone(), span: span.clone() }))
+            },
+            ("powmod", [b, e, m]) if *m != 0 => match b.pow_mod_ref(e, m) {
+                Some(res) => Integer::from(res),
+                None => return Some(Err(CalcError::Domain { name: self.clone(), span: span.clone() }))
+            },
+            ("modinv", _) | ("powmod", _) => return Some(Err(CalcError::Domain { name: self.clone(), span: span.clone() })),
+            _ => return None
+        };
+        Some(res.accuracy(ctx, span))
+    }
 
-                _ => return Err("Operator Undefined".to_string())
-            }
+    fn call_rational(&self, args: &[Rational], ctx: &Context, span: &Span) -> Option<Result<Rational, CalcError>> {
+        let res = match (self.as_str(), args) {
+            ("abs", [r]) => Rational::from(r.abs_ref()),
+            ("round", [r]) => r.round_by(ctx),
+            ("round", [r, d]) if *d.denom() == 1 => match d.numer().to_i32() {
+                Some(k) if k.unsigned_abs() <= ctx.max_exp() => r.round_digits(k, ctx),
+                _ => return Some(Err(CalcError::BeyondAccuracy { span: span.clone() }))
+            },
+            ("floor", [r]) => r.clone().floor(),
+            ("ceil", [r]) => r.clone().ceil(),
+            ("trunc", [r]) => r.clone().trunc(),
+            ("frac", [r]) => r.clone().fract_trunc(Integer::new()).0,
+            ("sign", [r]) => Rational::from(r.cmp0() as i32),
+            ("min", [first, rest @ ..]) => rest.iter().fold(first, |a, b| if b < a { b } else { a }).clone(),
+            ("max", [first, rest @ ..]) => rest.iter().fold(first, |a, b| if b > a { b } else { a }).clone(),
+            _ => return None
+        };
+        Some(res.accuracy(ctx, span))
+    }
+
+    fn radix(&self) -> (i32, &str) {
+        match self.get(..2) {
+            Some("0x") | Some("0X") => (16, &self[2..]),
+            Some("0o") | Some("0O") => (8, &self[2..]),
+            Some("0b") | Some("0B") => (2, &self[2..]),
+            _ => (10, &self[..]),
+        }
+    }
+
+    fn extract(&self, ctx: &Context, span: &Span) -> Result<Float, CalcError> {
+        let (radix, digits) = self.radix();
+        match Float::parse_radix(digits, radix) {
+            Ok(valid) => ctx.float(valid).accuracy(ctx, span),
+            Err(_) => Err(CalcError::InvalidNumber { span: span.clone() })
+        }
+    }
+
+    fn fraction(&self, ctx: &Context, span: &Span) -> Result<Rational, CalcError> {
+        let invalid = || CalcError::InvalidNumber { span: span.clone() };
+        let (radix, digits) = self.radix();
+        let (digits, exp) = match digits.find(['e', 'E']) {
+            Some(i) if radix == 10 => (&digits[..i], digits[i+1..].parse::<i64>().map_err(|_| invalid())?),
+            _ => (digits, 0),
+        };
+        let (int, frac) = match digits.find('.') {
+            Some(i) => (&digits[..i], &digits[i+1..]),
+            None => (digits, ""),
+        };
+        if int.len() + frac.len() == 0 || frac.contains('.') {
+            return Err(invalid());
+        }
+
+        let exp = match exp.checked_sub(frac.len() as i64) {
+            Some(exp) if exp.unsigned_abs() <= ctx.max_bits() as u64 => exp,
+            _ => return Err(CalcError::BeyondAccuracy { span: span.clone() })
+        };
+        let scale = Integer::from(Integer::u_pow_u(radix as u32, exp.unsigned_abs() as u32));
+        match Integer::parse_radix(format!("{}{}", int, frac), radix) {
+            Ok(valid) if exp < 0 => Rational::from((Integer::from(valid), scale)).accuracy(ctx, span),
+            Ok(valid) => Rational::from(Integer::from(valid) * scale).accuracy(ctx, span),
+            Err(_) => Err(invalid())
         }
-        Err("No Terminator".to_string())
+    }
+}
+
+impl Calc {
+    pub fn new(expr: String) -> Self {
+        Calc::with_context(expr, Context::default())
     }
 
-    pub fn run_round(&self, digits: Option<usize>) -> Result<String, String> {
-        match self.run() {
-            Ok(value) => Ok(value.to_string_round(digits)),
-            Err(err) => Err(err)
+    pub fn with_context(expr: String, ctx: Context) -> Self {
+        Self { expression: expr, context: ctx }
+    }
+
+    pub fn parse(&self) -> Result<Expr, CalcError> {
+        parse_with(&self.expression, &self.context)
+    }
+
+    pub fn run(&self) -> Result<Value, CalcError> {
+        self.parse()?.eval(&self.context)
+    }
+
+    pub fn run_round(&self, digits: Option<usize>) -> Result<String, CalcError> {
+        output(&self.run()?, digits, &self.context)
+    }
+}
+
+fn output(value: &Value, digits: Option<usize>, ctx: &Context) -> Result<String, CalcError> {
+    if let Some(x) = digits {
+        if x < 2 {
+            return Err(CalcError::Digits);
         }
     }
-}
\ No newline at end of file
+    let radix = ctx.output_radix();
+    if !(2..=36).contains(&radix) {
+        return Err(CalcError::Radix);
+    }
+    Ok(match value {
+        Value::Real(v) => v.to_string_round(digits, radix),
+        Value::Complex(z) if ctx.is_polar() => z.to_polar_round(digits, ctx),
+        Value::Complex(z) => z.to_string_round(digits, radix),
+        Value::Integer(n) if ctx.number_mode() == Mode::Programmer && radix != 10 && *n < 0 => {
+            n.clone().keep_bits(ctx.word_width().bits()).to_string_radix(radix)
+        },
+        Value::Integer(n) => n.to_string_radix(radix),
+        Value::Rational(r) => r.to_string_round(digits, ctx),
+        Value::Factors(_) => value.factors(radix),
+    })
+}
@@ -107,7 +107,7 @@ fn op_button_label(op: char, label: String) -> impl Widget<CalcState> {
                     if data.value != "0" {
                         match Calc::new(oper_repl(data.value.clone())).run_round(Some(7)) {
                             Ok(valid) => { data.value = valid.clone(); data.show = show_lens(valid) },
-                            Err(msg) => { data.value = String::from("0"); data.show = msg }
+                            Err(msg) => { data.value = String::from("0"); data.show = msg.to_string() }
                         }
                     }
                 },
@@ -277,7 +277,7 @@ fn build_calc() -> impl Widget<CalcState> {
                 digit_button(String::from("3")),
                 op_button('+'),
                 fun_button_label("Abs", String::from("abs(")),
-                fun_button_label("Log", String::from("logx(")),
+                fun_button_label("Log", String::from("log10(")),
                 fun_button_label("Sqrt", String::from("sqrt(")),
             ),
             1.0,
@@ -0,0 +1,730 @@
+use crate::context::{Context, Mode, Modulo};
+use crate::error::CalcError;
+use crate::expr::{parse_statement_with, Expr, Stmt};
+use crate::func::{arity, constant, is_exact};
+use crate::token::Span;
+use crate::value::Value;
+use crate::{output, Bigint, Other, Symbol};
+use rug::float::Round;
+use rug::Integer;
+use std::collections::HashMap;
+
+const MAX_DEPTH: usize = 1000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    pub params: Vec<String>,
+    pub body: Expr,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    context: Context,
+    vars: HashMap<String, Value>,
+    funcs: HashMap<String, Function>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    pub fn with_context(ctx: Context) -> Self {
+        Self { context: ctx, vars: HashMap::new(), funcs: HashMap::new() }
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn set_context(&mut self, ctx: Context) {
+        self.context = ctx;
+    }
+
+    pub fn var(&self, name: &str) -> Option<&Value> {
+        self.vars.get(name)
+    }
+
+    pub fn set_var(&mut self, name: &str, value: Value) {
+        self.vars.insert(name.to_string(), value);
+    }
+
+    pub fn remove_var(&mut self, name: &str) -> Option<Value> {
+        self.vars.remove(name)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.funcs.get(name)
+    }
+
+    pub fn define(&mut self, name: &str, params: Vec<String>, body: Expr) {
+        self.funcs.insert(name.to_string(), Function { params, body });
+    }
+
+    pub fn remove_function(&mut self, name: &str) -> Option<Function> {
+        self.funcs.remove(name)
+    }
+
+    pub fn run(&mut self, input: &str) -> Result<Option<Value>, CalcError> {
+        match parse_statement_with(input, &self.context)? {
+            Stmt::Expr(expr) => self.eval(&expr).map(Some),
+            Stmt::Assign(name, expr) => {
+                let value = self.eval(&expr)?;
+                self.vars.insert(name, value.clone());
+                Ok(Some(value))
+            }
+            Stmt::Define(name, params, body) => {
+                self.define(&name, params, body);
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn run_round(&mut self, input: &str, digits: Option<usize>) -> Result<Option<String>, CalcError> {
+        match self.run(input)? {
+            Some(value) => output(&value, digits, &self.context).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn eval(&self, expr: &Expr) -> Result<Value, CalcError> {
+        self.evaluate(expr, &HashMap::new(), 0)
+    }
+
+    fn evaluate(&self, expr: &Expr, locals: &HashMap<String, Value>, depth: usize) -> Result<Value, CalcError> {
+        if depth > MAX_DEPTH {
+            return Err(CalcError::TooDeep { span: expr.span() });
+        }
+        let value = self.compute(expr, locals, depth)?;
+        if self.context.number_mode() != Mode::Programmer {
+            return Ok(value);
+        }
+        let n = match value {
+            Value::Integer(n) => n,
+            Value::Real(v) => v.to_integer_round(Round::Zero).map_or_else(Integer::new, |(n, _)| n),
+            Value::Rational(r) => r.trunc().into_numer_denom().0,
+            Value::Complex(_) | Value::Factors(_) => return Ok(value),
+        };
+        Ok(Value::Integer(self.context.wrap(n)))
+    }
+
+    fn compute(&self, expr: &Expr, locals: &HashMap<String, Value>, depth: usize) -> Result<Value, CalcError> {
+        let ctx = &self.context;
+        match expr {
+            Expr::Number(text, span)
+                if matches!(ctx.number_mode(), Mode::Integer | Mode::Programmer) && !text.contains('.') => {
+                let value = text.fraction(ctx, span)?;
+                if *value.denom() != 1 {
+                    return text.extract(ctx, span).map(Value::Real);
+                }
+                Ok(Value::Integer(value.into_numer_denom().0))
+            }
+            Expr::Number(text, span) if ctx.number_mode() == Mode::Rational => {
+                text.fraction(ctx, span).map(Value::Rational)
+            }
+            Expr::Number(text, span) => text.extract(ctx, span).map(Value::Real),
+            Expr::Const(name, span) if name == "i" => match ctx.number_mode() {
+                Mode::Complex => Ok(Value::Complex(ctx.complex((0, 1)))),
+                _ => Err(CalcError::Domain { name: name.clone(), span: span.clone() })
+            },
+            Expr::Const(name, span) => match constant(name, ctx) {
+                Some(value) => Ok(Value::Real(value)),
+                None => Err(CalcError::UnknownVariable { name: name.clone(), span: span.clone() })
+            },
+            Expr::Var(name, span) => match locals.get(name).or_else(|| self.vars.get(name)) {
+                Some(value) => Ok(value.clone()),
+                None => Err(CalcError::UnknownVariable { name: name.clone(), span: span.clone() })
+            },
+            Expr::Neg(value) => Ok(-self.evaluate(value, locals, depth + 1)?),
+            Expr::Not(span, value) => match self.evaluate(value, locals, depth + 1)?.to_integer() {
+                Some(n) => Ok(Value::Integer(!n)),
+                None => Err(CalcError::Domain { name: "~".to_string(), span: span.clone() })
+            },
+            Expr::Binary(ch, span, left, right) => {
+                let left = self.evaluate(left, locals, depth + 1)?;
+                let right = self.evaluate(right, locals, depth + 1)?;
+                match (*ch, self.context.modulo_style()) {
+                    (b'%', Modulo::Floored) => self.binary(b'm', "%", left, right, span),
+                    (ch, _) => self.binary(ch, ch.name(), left, right, span),
+                }
+            }
+            Expr::Call(name, span, args) => self.call(name, span, args, locals, depth),
+        }
+    }
+
+    fn call(&self, name: &str, span: &Span, args: &[Expr], locals: &HashMap<String, Value>, depth: usize)
+        -> Result<Value, CalcError> {
+        if arity(name).is_some() {
+            let mut values = Vec::with_capacity(args.len());
+            for arg in args {
+                values.push(self.evaluate(arg, locals, depth + 1)?);
+            }
+            return self.builtin(name, values, span);
+        }
+
+        let func = match self.funcs.get(name) {
+            Some(func) => func,
+            None => return Err(CalcError::UnknownFunction { name: name.to_string(), span: span.clone() })
+        };
+        if func.params.len() != args.len() {
+            return Err(CalcError::Arity { name: name.to_string(), span: span.clone() });
+        }
+
+        let mut scope = HashMap::new();
+        for (param, arg) in func.params.iter().zip(args) {
+            scope.insert(param.clone(), self.evaluate(arg, locals, depth + 1)?);
+        }
+        self.evaluate(&func.body, &scope, depth + 1).map_err(|err| match err {
+            CalcError::TooDeep { .. } => CalcError::Recursion { name: name.to_string(), span: span.clone() },
+            err => err,
+        })
+    }
+
+    fn binary(&self, op: u8, name: &str, left: Value, right: Value, span: &Span) -> Result<Value, CalcError> {
+        self.operate(op, left, right, span).map_err(|err| err.with_operator(name))
+    }
+
+    fn operate(&self, op: u8, left: Value, right: Value, span: &Span) -> Result<Value, CalcError> {
+        let ctx = &self.context;
+        let ints = if left.is_integer() && right.is_integer() || b"&|x<>".contains(&op) {
+            match (left.to_integer(), right.to_integer()) {
+                (Some(c2), Some(c1)) => Some((c2, c1)),
+                _ => return Err(CalcError::Domain { name: op.name().to_string(), span: span.clone() })
+            }
+        } else {
+            None
+        };
+        if let Some(res) = ints.and_then(|(c2, c1)| op.integer(&c2, &c1, ctx, span)) {
+            return res.map(Value::Integer);
+        }
+        if let (Value::Rational(_), _) | (_, Value::Rational(_)) = (&left, &right) {
+            if let (Some(c2), Some(c1)) = (left.to_rational(), right.to_rational()) {
+                if let Some(res) = op.rational(&c2, &c1, ctx, span) {
+                    return res.map(Value::Rational);
+                }
+            }
+        }
+        match (left.to_real(ctx), right.to_real(ctx)) {
+            (Some(c2), Some(c1))
+                if ctx.number_mode() != Mode::Complex || op != b'^' || c2 >= 0.0 || c1.is_integer() => {
+                op.computing(c2, c1, ctx, span).map(Value::Real)
+            }
+            _ => op.complex(left.into_complex(ctx), right.into_complex(ctx), ctx, span).map(Value::demote),
+        }
+    }
+
+    fn builtin(&self, name: &str, args: Vec<Value>, span: &Span) -> Result<Value, CalcError> {
+        let ctx = &self.context;
+        match (name, args.as_slice()) {
+            ("mod", [a, b]) => return self.binary(b'm', "mod", a.clone(), b.clone(), span),
+            ("rem", [a, b]) => return self.binary(b'%', "rem", a.clone(), b.clone(), span),
+            _ => {}
+        }
+        let name = match (name, args.len()) {
+            ("log", 1) if ctx.is_log_base2() => "log2",
+            ("log", 1) | ("logx", _) | ("lg", _) => "log10",
+            (name, _) => name,
+        }.to_string();
+        let exact = is_exact(&name);
+        let ints = args.iter()
+            .map(|v| if v.is_integer() || exact { v.to_integer() } else { None })
+            .collect::<Option<Vec<_>>>();
+        if let ("factor", Some([n])) = (name.as_str(), ints.as_deref()) {
+            return n.factor(span).map(Value::Factors);
+        } else if let Some(res) = ints.and_then(|ints| name.call_integer(&ints, ctx, span)) {
+            return res.map(Value::Integer);
+        } else if exact {
+            return Err(CalcError::Domain { name, span: span.clone() });
+        }
+        if args.iter().any(|v| matches!(v, Value::Rational(_))) {
+            let rats = args.iter().map(Value::to_rational).collect::<Option<Vec<_>>>();
+            if let Some(res) = rats.and_then(|rats| name.call_rational(&rats, ctx, span)) {
+                return res.map(Value::Rational);
+            }
+        }
+        if let Some(reals) = args.iter().map(|v| v.to_real(ctx)).collect() {
+            match name.call(reals, ctx, span) {
+                Err(CalcError::Domain { .. }) if ctx.number_mode() == Mode::Complex => {}
+                res => return res.map(Value::Real),
+            }
+        }
+        let args = args.into_iter().map(|v| v.into_complex(ctx)).collect();
+        name.call_complex(args, ctx, span).map(Value::demote)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::{Angle, Fraction, Implicit, Rounding, Width};
+    use rug::{Float, Rational};
+
+    fn run(input: &str, ctx: Context) -> Result<Value, CalcError> {
+        Session::with_context(ctx).run(input).map(|value| value.expect("no value"))
+    }
+
+    #[test]
+    fn errors_carry_spans() {
+        let ctx = Context::default();
+        assert_eq!(run("1/0", ctx.clone()), Err(CalcError::DivideByZero { op: "/".to_string(), span: 1..2 }));
+        assert_eq!(run("10^1000", ctx.clone()), Err(CalcError::Overflow { op: "^".to_string(), span: 2..3 }));
+        assert_eq!(run("sqrt(-1)", ctx.clone()), Err(CalcError::Domain { name: "sqrt".to_string(), span: 0..4 }));
+        assert_eq!(run("foo(1)", ctx.clone()), Err(CalcError::UnknownFunction { name: "foo".to_string(), span: 0..3 }));
+        assert_eq!(run("1 +", ctx), Err(CalcError::Syntax { span: 3..3 }));
+    }
+
+    #[test]
+    fn trailing_assign_is_an_expression() {
+        let mut session = Session::new();
+        assert!(matches!(session.run("sin(P)="), Ok(Some(Value::Real(_)))));
+        assert!(matches!(session.run("P="), Ok(Some(Value::Real(_)))));
+        assert_eq!(session.run("f(x)="), Err(CalcError::UnknownFunction { name: "f".to_string(), span: 0..1 }));
+        assert_eq!(session.run("f(x)=x+1"), Ok(None));
+        assert!(session.function("f").is_some());
+    }
+
+    fn shown(input: &str, ctx: Context) -> String {
+        match Session::with_context(ctx).run_round(input, Some(7)) {
+            Ok(Some(text)) => text,
+            res => panic!("{}: {:?}", input, res),
+        }
+    }
+
+    #[test]
+    fn angle_modes() {
+        let degree = Context::default().angle(Angle::Degree);
+        assert_eq!(real("sin(180)", degree.clone()), 0);
+        assert_eq!(real("cos(180)", degree.clone()), -1);
+        assert_eq!(real("sin(-270)", degree.clone()), 1);
+        assert_eq!(shown("sin(30)", degree.clone()), "0.5");
+        assert_eq!(run("tan(90)", degree.clone()), Err(CalcError::Domain { name: "tan".to_string(), span: 0..3 }));
+
+        let gradian = Context::default().angle(Angle::Gradian);
+        assert_eq!(real("sin(200)", gradian.clone()), 0);
+        assert_eq!(real("cos(100)", gradian.clone()), 0);
+        assert_eq!(shown("sin(50)", gradian.clone()), shown("sqrt(2)/2", gradian.clone()));
+
+        let radian = Context::default();
+        assert_eq!(real("sin(0)", radian.clone()), 0);
+        assert_eq!(shown("deg(P)", radian.clone()), "180");
+        assert_eq!(shown("rad(180)", radian.clone()), "3.141593");
+        assert_eq!(shown("deg(P)", degree.clone()), "180");
+    }
+
+    #[test]
+    fn inverse_trig_follows_the_angle_mode() {
+        let degree = Context::default().angle(Angle::Degree);
+        assert_eq!(shown("asin(1)", degree.clone()), "90");
+        assert_eq!(shown("acos(0.5)", degree.clone()), "60");
+        assert_eq!(shown("atan(1)", degree.clone()), "45");
+        assert_eq!(shown("atan2(1, -1)", degree), "135");
+        assert_eq!(shown("asin(1)", Context::default().angle(Angle::Gradian)), "100");
+        assert_eq!(shown("asin(1)", Context::default()), "1.570796");
+    }
+
+    #[test]
+    fn imaginary_unit_is_only_reserved_in_complex_mode() {
+        let mut session = Session::new();
+        session.run("i=5").unwrap();
+        session.run("f(i)=i+1").unwrap();
+        assert!(matches!(session.run("i*2"), Ok(Some(Value::Real(v))) if v == 10));
+        assert!(matches!(session.run("f(2)"), Ok(Some(Value::Real(v))) if v == 3));
+
+        let mut session = Session::with_context(Context::default().mode(Mode::Complex));
+        assert_eq!(session.run("i=5"), Err(CalcError::Reserved { name: "i".to_string(), span: 0..1 }));
+        assert!(matches!(session.run("i*i"), Ok(Some(Value::Real(v))) if v == -1));
+    }
+
+    #[test]
+    fn complex_results() {
+        let complex = Context::default().mode(Mode::Complex);
+        assert_eq!(shown("sqrt(-1)", complex.clone()), "i");
+        assert_eq!(shown("ln(-2)", complex.clone()), "0.693147 + 3.141593i");
+        assert_eq!(shown("3+4i", complex.clone()), "3 + 4i");
+        assert_eq!(shown("2-3i", complex.clone()), "2 - 3i");
+        assert_eq!(shown("-i", complex.clone()), "-i");
+        assert_eq!(shown("(1+i)^2", complex.clone()), "2i");
+        assert_eq!(run("ln(-2)", Context::default()), Err(CalcError::Domain { name: "ln".to_string(), span: 0..2 }));
+
+        let polar = complex.polar(true);
+        assert_eq!(shown("i", polar.clone()), "1∠1.570796");
+        assert_eq!(shown("3+4i", polar.clone().angle(Angle::Degree)), "5∠53.130102");
+        assert_eq!(shown("-2i", polar.angle(Angle::Degree)), "2∠-90");
+    }
+
+    #[test]
+    fn exact_integers() {
+        let integer = Context::default().mode(Mode::Integer);
+        let fac = Integer::from(Integer::factorial(500));
+        assert_eq!(fac.to_string().len(), 1135);
+        assert_eq!(run("fac(500)", integer.clone()), Ok(Value::Integer(fac.clone())));
+        assert_eq!(run("500!", integer.clone()), Ok(Value::Integer(fac)));
+        assert_eq!(run("2^4000", integer.clone()), Ok(Value::Integer(Integer::from(1) << 4000)));
+        assert_eq!(run("6/3", integer.clone()), Ok(Value::Integer(Integer::from(2))));
+        assert_eq!(shown("7/2", integer.clone()), "3.5");
+        assert_eq!(run("fac(10^9)", integer), Err(CalcError::BeyondAccuracy { span: 0..3 }));
+    }
+
+    #[test]
+    fn exact_rationals() {
+        let rational = Context::default().mode(Mode::Rational);
+        assert_eq!(run("1/3*3", rational.clone()), Ok(Value::Rational(Rational::from(1))));
+        assert_eq!(run("0.1+0.2", rational.clone()), Ok(Value::Rational(Rational::from((3, 10)))));
+        assert_eq!(shown("1/3*3", rational.clone()), "1");
+        assert_eq!(shown("4/3", rational.clone()), "4/3");
+
+        let mixed = rational.clone().fraction(Fraction::Mixed);
+        assert_eq!(shown("4/3", mixed.clone()), "1 1/3");
+        assert_eq!(shown("-4/3", mixed.clone()), "-1 1/3");
+        assert_eq!(shown("-1/3", mixed), "-1/3");
+        assert_eq!(shown("1/3", rational.fraction(Fraction::Decimal)), "0.333333");
+    }
+
+    #[test]
+    fn radix_literals() {
+        let ctx = Context::default();
+        assert!(matches!(run("0xFF", ctx.clone()), Ok(Value::Real(v)) if v == 255));
+        assert!(matches!(run("0o17", ctx.clone()), Ok(Value::Real(v)) if v == 15));
+        assert!(matches!(run("0b1010", ctx.clone()), Ok(Value::Real(v)) if v == 10));
+        assert!(matches!(run("0x1.8", ctx.clone()), Ok(Value::Real(v)) if v == 1.5));
+        assert_eq!(run("0b12", ctx), Err(CalcError::InvalidNumber { span: 0..4 }));
+        assert_eq!(run("0xff", Context::default().mode(Mode::Integer)), Ok(Value::Integer(Integer::from(255))));
+    }
+
+    #[test]
+    fn output_radix() {
+        let ctx = Context::default().mode(Mode::Integer);
+        let mut session = Session::with_context(ctx.clone().radix(16));
+        assert_eq!(session.run_round("255", None), Ok(Some("ff".to_string())));
+        let mut session = Session::with_context(ctx.clone().radix(2));
+        assert_eq!(session.run_round("0xA", None), Ok(Some("1010".to_string())));
+        let mut session = Session::with_context(ctx.radix(37));
+        assert_eq!(session.run_round("1", None), Err(CalcError::Radix));
+    }
+
+    fn programmer(input: &str, width: Width, signed: bool) -> Integer {
+        let ctx = Context::default().mode(Mode::Programmer).width(width).signed(signed);
+        match run(input, ctx) {
+            Ok(Value::Integer(n)) => n,
+            res => panic!("{}: {:?}", input, res),
+        }
+    }
+
+    #[test]
+    fn bitwise_operators() {
+        assert_eq!(programmer("0xFF & 0x0F", Width::W64, true), 15);
+        assert_eq!(programmer("6 xor 3", Width::W64, true), 5);
+        assert_eq!(programmer("1 << 4 | 1", Width::W64, true), 17);
+        assert_eq!(programmer("1 + 2 << 1", Width::W64, true), 6);
+        assert_eq!(programmer("1 | 2 & 3", Width::W64, true), 3);
+        assert_eq!(programmer("~0", Width::W64, true), -1);
+        assert_eq!(programmer("-7 / 2", Width::W64, true), -3);
+    }
+
+    #[test]
+    fn fixed_width_wrapping() {
+        assert_eq!(programmer("127 + 1", Width::W8, true), -128);
+        assert_eq!(programmer("0 - 1", Width::W8, false), 255);
+        assert_eq!(programmer("1 << 16", Width::W16, false), 0);
+        assert_eq!(programmer("0xFFFFFFFF + 1", Width::W32, false), 0);
+
+        let ctx = Context::default().mode(Mode::Programmer).width(Width::W8).radix(16);
+        assert_eq!(Session::with_context(ctx).run_round("-1", None), Ok(Some("ff".to_string())));
+    }
+
+    fn real(input: &str, ctx: Context) -> Float {
+        match run(input, ctx) {
+            Ok(Value::Real(v)) => v,
+            res => panic!("{}: {:?}", input, res),
+        }
+    }
+
+    #[test]
+    fn exponent_literals() {
+        let ctx = Context::default();
+        assert_eq!(real("1.5e-30", ctx.clone()), real("1.5/10^30", ctx.clone()));
+        assert_eq!(real("6.02E23", ctx.clone()), real("602*10^21", ctx.clone()));
+        assert_eq!(real("2e", ctx.clone()), real("2*e", ctx.clone()));
+        assert_eq!(real("2exp(1)", ctx.clone()), real("2*e", ctx.clone()));
+
+        let exact = ctx.mode(Mode::Rational);
+        assert_eq!(run("1.5e-30", exact.clone()), run("3/(2*10^30)", exact.clone()));
+        assert_eq!(run("6.02E23", exact.clone()), run("602*10^21", exact.clone()));
+        let huge = "1.5e-9223372036854775808";
+        assert_eq!(run(huge, exact), Err(CalcError::BeyondAccuracy { span: 0..huge.len() }));
+    }
+
+    #[test]
+    fn implicit_multiplication() {
+        let ctx = Context::default();
+        assert_eq!(real("2P", ctx.clone()), real("2*P", ctx.clone()));
+        assert_eq!(real("3(4+5)", ctx.clone()), 27);
+        assert_eq!(real("2sqrt(9)", ctx.clone()), 6);
+        assert_eq!(real("(2)(3)", ctx.clone()), 6);
+        assert_eq!(real("6/2(3)", ctx.clone()), 9);
+        assert_eq!(real("6/2(3)", ctx.clone().implicit(Implicit::Tight)), 1);
+        assert_eq!(real("P(2)", ctx.clone()), real("2*P", ctx.clone()));
+        assert_eq!(real("e(1+1)", ctx.clone()), real("2*e", ctx.clone()));
+        assert_eq!(real("phi(3)", ctx.clone()), real("3*phi", ctx.clone()));
+        assert_eq!(real("gamma(5)", ctx.clone()), 24);
+        assert_eq!(run("2 3", ctx.clone()), Err(CalcError::Syntax { span: 2..3 }));
+        assert_eq!(run("(1)2", ctx), Err(CalcError::Syntax { span: 3..4 }));
+
+        let mut session = Session::new();
+        session.run("x=3").unwrap();
+        assert!(matches!(session.run("2x"), Ok(Some(Value::Real(v))) if v == 6));
+    }
+
+    #[test]
+    fn power_and_unary_minus() {
+        let ctx = Context::default();
+        assert_eq!(real("2^3^2", ctx.clone()), 512);
+        assert_eq!(real("-2^2", ctx.clone()), -4);
+        assert_eq!(real("--3", ctx.clone()), 3);
+        assert_eq!(real("-(1+2)", ctx.clone()), -3);
+        assert_eq!(real("2^-1", ctx.clone()), 0.5);
+        assert_eq!(real("2*-3", ctx), -6);
+
+        let legacy = Context::default().legacy(true);
+        assert_eq!(real("2^3^2", legacy.clone()), 64);
+        assert_eq!(real("-2^2", legacy.clone()), 4);
+        assert_eq!(run("--3", legacy), Err(CalcError::Syntax { span: 1..2 }));
+    }
+
+    #[test]
+    fn bare_log_base() {
+        assert_eq!(real("log(1000)", Context::default()), 3);
+        assert_eq!(real("log(8)", Context::default().log_base2(true)), 3);
+        assert_eq!(real("log(1000)", Context::default().legacy(true)), 3);
+    }
+
+    #[test]
+    fn postfix_operators() {
+        let ctx = Context::default();
+        assert_eq!(real("3!", ctx.clone()), 6);
+        assert_eq!(real("3!^2", ctx.clone()), 36);
+        assert_eq!(real("-3!", ctx.clone()), -6);
+        assert_eq!(real("5!!", ctx.clone()), 15);
+        assert_eq!(real("50%", ctx.clone()), 0.5);
+        assert_eq!(real("50%*200", ctx.clone()), 100);
+        assert_eq!(real("10 % 3", ctx.clone()), 1);
+        assert_eq!(programmer("4!", Width::W64, true), 24);
+        assert_eq!(run(&format!("1{}", "!".repeat(5000)), ctx), Err(CalcError::TooDeep { span: 1999..2000 }));
+    }
+
+    #[test]
+    fn gamma_family() {
+        let ctx = Context::default();
+        let domain = |name: &str, span: Span| Err(CalcError::Domain { name: name.to_string(), span });
+        assert_eq!(real("gamma(5)", ctx.clone()), 24);
+        assert_eq!(shown("gamma(0.5)^2", ctx.clone()), "3.141593");
+        assert_eq!(shown("digamma(1)", ctx.clone()), "-0.577216");
+        assert_eq!(shown("lgamma(10)", ctx.clone()), shown("ln(362880)", ctx.clone()));
+        assert_eq!(shown("fac(0.5)", ctx.clone()), "0.886227");
+        assert_eq!(run("gamma(0)", ctx.clone()), domain("gamma", 0..5));
+        assert_eq!(run("gamma(-2)", ctx.clone()), domain("gamma", 0..5));
+        assert_eq!(run("lgamma(-1)", ctx.clone()), domain("lgamma", 0..6));
+        assert_eq!(run("fac(-1)", ctx.clone()), domain("fac", 0..3));
+
+        assert_eq!(shown("beta(2, 3)", ctx.clone()), "0.083333");
+        assert_eq!(shown("beta(0.5, 0.5)", ctx.clone()), "3.141593");
+        assert_eq!(shown("beta(-0.5, 1)", ctx.clone()), "-2");
+        assert_eq!(shown("beta(-0.5, -1.5)", ctx.clone()), "0");
+        assert_eq!(run("beta(-1, 2)", ctx), domain("beta", 0..4));
+    }
+
+    #[test]
+    fn special_functions() {
+        let ctx = Context::default();
+        let domain = |name: &str, span: Span| Err(CalcError::Domain { name: name.to_string(), span });
+        assert_eq!(real("erf(0)", ctx.clone()), 0);
+        assert_eq!(shown("erf(1)", ctx.clone()), "0.842701");
+        assert_eq!(shown("erfc(1)", ctx.clone()), "0.157299");
+        assert_eq!(shown("zeta(2)", ctx.clone()), "1.644934");
+        assert_eq!(shown("zeta(-1)", ctx.clone()), "-0.083333");
+        assert_eq!(run("zeta(1)", ctx.clone()), domain("zeta", 0..4));
+
+        assert_eq!(real("j0(0)", ctx.clone()), 1);
+        assert_eq!(shown("j1(1)", ctx.clone()), "0.440051");
+        assert_eq!(shown("jn(2, 1)", ctx.clone()), "0.114903");
+        assert_eq!(shown("y0(1)", ctx.clone()), "0.088257");
+        assert_eq!(run("y0(0)", ctx.clone()), domain("y0", 0..2));
+        assert_eq!(run("yn(1, 0)", ctx.clone()), domain("yn", 0..2));
+
+        assert_eq!(shown("agm(1, 2)", ctx.clone()), "1.456791");
+        assert_eq!(run("agm(-1, 2)", ctx.clone()), domain("agm", 0..3));
+        assert_eq!(shown("eint(1)", ctx.clone()), "1.895118");
+        assert_eq!(run("eint(0)", ctx.clone()), domain("eint", 0..4));
+        assert_eq!(shown("li2(1)", ctx.clone()), "1.644934");
+        assert_eq!(shown("li2(-1)", ctx.clone()), "-0.822467");
+        assert_eq!(run("li2(2)", ctx), domain("li2", 0..3));
+    }
+
+    #[test]
+    fn lambert_w_branches() {
+        let ctx = Context::default();
+        let domain = Err(CalcError::Domain { name: "lambertw".to_string(), span: 0..8 });
+        assert_eq!(real("lambertw(0)", ctx.clone()), 0);
+        assert_eq!(shown("lambertw(1)", ctx.clone()), "0.567143");
+        assert_eq!(shown("lambertw(10)", ctx.clone()), "1.745528");
+        assert_eq!(shown("lambertw(-0.1)", ctx.clone()), "-0.111833");
+        assert_eq!(shown("lambertw(-0.1, -1)", ctx.clone()), "-3.577152");
+        assert_eq!(run("lambertw(-1)", ctx.clone()), domain);
+        assert_eq!(run("lambertw(1, -1)", ctx.clone()), domain);
+        assert_eq!(run("lambertw(1, 2)", ctx.clone()), domain);
+
+        let mut session = Session::with_context(ctx);
+        for (input, expected) in [("w=lambertw(2)", "2"), ("w=lambertw(-0.1, -1)", "-0.1"), ("w=lambertw(-0.3)", "-0.3")] {
+            session.run(input).unwrap();
+            assert_eq!(session.run_round("w*exp(w)", Some(500)), Ok(Some(expected.to_string())));
+        }
+    }
+
+    #[test]
+    fn number_theory() {
+        let ctx = Context::default();
+        assert_eq!(run("nCr(5, 2)", ctx.clone()), Ok(Value::Integer(Integer::from(10))));
+        assert_eq!(run("nPr(5, 2)", ctx.clone()), Ok(Value::Integer(Integer::from(20))));
+        assert_eq!(run("isprime(97)", ctx.clone()), Ok(Value::Integer(Integer::from(1))));
+        assert_eq!(run("powmod(2, 10, 1000)", ctx.clone()), Ok(Value::Integer(Integer::from(24))));
+        assert_eq!(run("modinv(3, 7)", ctx.clone()), Ok(Value::Integer(Integer::from(5))));
+        assert_eq!(run("modinv(2, 4)", ctx.clone()), Err(CalcError::Domain { name: "modinv".to_string(), span: 0..6 }));
+        assert_eq!(run("isprime(2^2203-1)", ctx.clone()), Ok(Value::Integer(Integer::from(1))));
+        assert_eq!(run("isprime(2^2203+1)", ctx.clone()), Ok(Value::Integer(Integer::from(0))));
+        assert!(matches!(run("nextprime(2^2000)", ctx.clone()), Ok(Value::Integer(n)) if n > (Integer::from(1) << 2000)));
+        assert_eq!(run("factor(3*nextprime(2^140))", ctx), Err(CalcError::BeyondAccuracy { span: 0..6 }));
+        assert_eq!(Session::new().run_round("factor(84)", None), Ok(Some("2^2 * 3 * 7".to_string())));
+        assert_eq!(Session::new().run_round("factor(2^2000)", None), Ok(Some("2^2000".to_string())));
+
+        let integer = Context::default().mode(Mode::Integer);
+        assert_eq!(run("isprime(2^4096)", integer.clone()), Err(CalcError::BeyondAccuracy { span: 0..7 }));
+        assert_eq!(run("factor(2^5000)", integer), Err(CalcError::BeyondAccuracy { span: 0..6 }));
+    }
+
+    #[test]
+    fn modulo_and_floor_division() {
+        let ctx = Context::default();
+        assert_eq!(real("-7 // 2", ctx.clone()), -4);
+        assert_eq!(real("mod(-7, 2)", ctx.clone()), 1);
+        assert_eq!(real("rem(-7, 2)", ctx.clone()), -1);
+        assert_eq!(real("-7 % 2", ctx.clone()), -1);
+        assert_eq!(real("-7 % 2", ctx.clone().modulo(Modulo::Floored)), 1);
+
+        let integer = ctx.clone().mode(Mode::Integer);
+        assert_eq!(run("-7 // 2", integer.clone()), Ok(Value::Integer(Integer::from(-4))));
+        assert_eq!(run("mod(-7, 2)", integer.clone()), Ok(Value::Integer(Integer::from(1))));
+        assert_eq!(run("-7 % 2", integer.modulo(Modulo::Floored)), Ok(Value::Integer(Integer::from(1))));
+    }
+
+    #[test]
+    fn divide_by_zero_names_the_operator() {
+        let error = |op: &str, span: Span| Err(CalcError::DivideByZero { op: op.to_string(), span });
+        let ctx = Context::default();
+        assert_eq!(run("7 // 0", ctx.clone()), error("//", 2..4));
+        assert_eq!(run("7 % 0", ctx.clone().modulo(Modulo::Floored)), error("%", 2..3));
+        assert_eq!(run("mod(1, 0)", ctx.clone()), error("mod", 0..3));
+        assert_eq!(run("rem(1, 0)", ctx.clone().mode(Mode::Integer)), error("rem", 0..3));
+    }
+
+    #[test]
+    fn rounding_rules() {
+        let half_up = Context::default();
+        let half_even = Context::default().round_rule(Rounding::HalfEven);
+        let zero = Context::default().round_rule(Rounding::Zero);
+        assert_eq!(real("round(2.5)", half_up.clone()), 3);
+        assert_eq!(real("round(-2.5)", half_up.clone()), -3);
+        assert_eq!(real("round(2.5)", half_even.clone()), 2);
+        assert_eq!(real("round(3.5)", half_even.clone()), 4);
+        assert_eq!(real("round(-2.5)", half_even.clone()), -2);
+        assert_eq!(real("round(2.7)", zero.clone()), 2);
+        assert_eq!(real("round(-2.7)", zero.clone()), -2);
+
+        let exact = |input: &str, ctx: Context| run(input, ctx.mode(Mode::Rational));
+        assert_eq!(exact("round(2.675, 2)", half_up.clone()), Ok(Value::Rational(Rational::from((67, 25)))));
+        assert_eq!(exact("round(2.665, 2)", half_even.clone()), Ok(Value::Rational(Rational::from((133, 50)))));
+        assert_eq!(exact("round(2.679, 2)", zero), Ok(Value::Rational(Rational::from((267, 100)))));
+
+        let integer = |input: &str, ctx: Context| run(input, ctx.mode(Mode::Integer));
+        assert_eq!(integer("round(1250, -2)", half_up.clone()), Ok(Value::Integer(Integer::from(1300))));
+        assert_eq!(integer("round(1250, -2)", half_even), Ok(Value::Integer(Integer::from(1200))));
+    }
+
+    #[test]
+    fn rounding_digits_are_bounded_in_every_mode() {
+        let error = Err(CalcError::BeyondAccuracy { span: 0..5 });
+        assert_eq!(run("round(15, 1000)", Context::default()), error);
+        assert_eq!(run("round(15, -1000)", Context::default()), error);
+        assert_eq!(run("round(15, 1000)", Context::default().mode(Mode::Rational)), error);
+        assert_eq!(run("round(15, -1000)", Context::default().mode(Mode::Integer)), error);
+        assert_eq!(run("round(15, 1000)", Context::default().mode(Mode::Integer)), error);
+    }
+
+    #[test]
+    fn integer_parts() {
+        let ctx = Context::default();
+        assert_eq!(real("floor(-2.5)", ctx.clone()), -3);
+        assert_eq!(real("ceil(-2.5)", ctx.clone()), -2);
+        assert_eq!(real("trunc(-2.5)", ctx.clone()), -2);
+        assert_eq!(real("frac(-2.5)", ctx.clone()), -0.5);
+        assert_eq!(real("sign(-3)", ctx.clone()), -1);
+        assert_eq!(real("sign(0)", ctx), 0);
+    }
+
+    #[test]
+    fn reciprocal_inverse_functions() {
+        let ctx = Context::default();
+        let domain = |name: &str| Err(CalcError::Domain { name: name.to_string(), span: 0..name.len() });
+        assert_eq!(shown("acsc(2)", ctx.clone()), "0.523599");
+        assert_eq!(shown("acoth(2)", ctx.clone()), "0.549306");
+        assert_eq!(shown("asech(0.5)", ctx.clone()), "1.316958");
+        assert_eq!(shown("acsch(1)", ctx.clone()), shown("asinh(1)", ctx.clone()));
+        assert_eq!(run("acsc(0.5)", ctx.clone()), domain("acsc"));
+        assert_eq!(run("asec(0.5)", ctx.clone()), domain("asec"));
+        assert_eq!(run("acoth(1)", ctx.clone()), domain("acoth"));
+        assert_eq!(run("acoth(0.5)", ctx.clone()), domain("acoth"));
+        assert_eq!(run("asech(0)", ctx.clone()), domain("asech"));
+        assert_eq!(run("asech(2)", ctx.clone()), domain("asech"));
+        assert_eq!(run("acsch(0)", ctx.clone()), domain("acsch"));
+        assert_eq!(shown("haversine(P)", ctx.clone()), "1");
+
+        let degree = ctx.angle(Angle::Degree);
+        assert_eq!(shown("acsc(2)", degree.clone()), "30");
+        assert_eq!(shown("asec(2)", degree.clone()), "60");
+        assert_eq!(shown("acot(1)", degree.clone()), "45");
+        assert_eq!(shown("acot(0)", degree.clone()), "90");
+        assert_eq!(shown("versin(180)", degree.clone()), "2");
+        assert_eq!(shown("haversine(90)", degree), "0.5");
+    }
+
+    // Runs on a thread sized like the GUI main thread; the default test
+    // thread stack is too small for a debug build at the full depth.
+    fn deep<F: FnOnce() + Send + 'static>(test: F) {
+        std::thread::Builder::new().stack_size(8 << 20).spawn(test).unwrap().join().unwrap();
+    }
+
+    #[test]
+    fn recursion_is_limited() {
+        deep(|| {
+            let mut session = Session::new();
+            session.run("f(x)=f(x)+1").unwrap();
+            assert_eq!(session.run("f(1)"), Err(CalcError::Recursion { name: "f".to_string(), span: 5..6 }));
+        });
+    }
+
+    #[test]
+    fn nested_bodies_share_the_depth_limit() {
+        deep(|| {
+            let mut session = Session::new();
+            let body = format!("{}g(x){}", "-(".repeat(300), ")".repeat(300));
+            session.run(&format!("g(x)={}", body)).unwrap();
+            assert_eq!(session.run("g(1)"), Err(CalcError::Recursion { name: "g".to_string(), span: 605..606 }));
+        });
+    }
+
+    #[test]
+    fn errors_keep_messages() {
+        assert_eq!(CalcError::Overflow { op: "^".to_string(), span: 0..1 }.to_string(), "Beyond Accuracy");
+        assert_eq!(CalcError::DivideByZero { op: "/".to_string(), span: 0..1 }.to_string(), "Divide By Zero");
+        assert_eq!(CalcError::Domain { name: "ln".to_string(), span: 0..2 }.to_string(), "Parameter Error");
+    }
+}
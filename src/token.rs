@@ -0,0 +1,93 @@
+use crate::error::CalcError;
+use std::ops::Range;
+
+pub type Span = Range<usize>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Number(String),
+    Ident(String),
+    Operator(u8),
+    LParen,
+    RParen,
+    Comma,
+    Assign,
+}
+
+pub fn tokenize(expr: &str) -> Result<Vec<(Token, Span)>, CalcError> {
+    let bytes = expr.as_bytes();
+    let mut tokens = Vec::new();
+    let mut index: usize = 0;
+
+    while index < bytes.len() {
+        let locat = index;
+        let token = match bytes[index] {
+            b'0' if matches!(bytes.get(index+1), Some(b'x') | Some(b'X') | Some(b'o') | Some(b'O') | Some(b'b') | Some(b'B')) => {
+                let hex = matches!(bytes[index+1], b'x' | b'X');
+                index += 2;
+                while index < bytes.len() && (bytes[index].is_ascii_digit() || bytes[index] == b'.'
+                    || hex && bytes[index].is_ascii_hexdigit()) {
+                    index += 1;
+                }
+                tokens.push((Token::Number(expr[locat..index].to_string()), locat..index));
+                continue;
+            }
+
+            b'0'..=b'9' | b'.' => {
+                while index < bytes.len() && (bytes[index].is_ascii_digit() || bytes[index] == b'.') {
+                    index += 1;
+                }
+                if let Some(b'e') | Some(b'E') = bytes.get(index) {
+                    let mut end = index + 1;
+                    if let Some(b'+') | Some(b'-') = bytes.get(end) {
+                        end += 1;
+                    }
+                    if let Some(b'0'..=b'9') = bytes.get(end) {
+                        index = end;
+                        while index < bytes.len() && bytes[index].is_ascii_digit() {
+                            index += 1;
+                        }
+                    }
+                }
+                tokens.push((Token::Number(expr[locat..index].to_string()), locat..index));
+                continue;
+            }
+
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => {
+                while index < bytes.len() && (bytes[index].is_ascii_alphanumeric() || bytes[index] == b'_') {
+                    index += 1;
+                }
+                let token = match &expr[locat..index] {
+                    "xor" => Token::Operator(b'x'),
+                    name => Token::Ident(name.to_string()),
+                };
+                tokens.push((token, locat..index));
+                continue;
+            }
+
+            b'/' if bytes.get(index+1) == Some(&b'/') => {
+                index += 1;
+                Token::Operator(b'\\')
+            }
+            ch @ b'+' | ch @ b'-' | ch @ b'*' | ch @ b'/' | ch @ b'%' | ch @ b'^' => Token::Operator(ch),
+            ch @ b'&' | ch @ b'|' | ch @ b'~' | ch @ b'!' => Token::Operator(ch),
+            ch @ b'<' | ch @ b'>' if bytes.get(index+1) == Some(&ch) => {
+                index += 1;
+                Token::Operator(ch)
+            }
+            b'(' => Token::LParen,
+            b')' => Token::RParen,
+            b',' => Token::Comma,
+            b'=' => Token::Assign,
+            b' ' | b'\t' => { index += 1; continue; },
+            b'\n' | b'\r' => break,
+            _ => {
+                let len = expr[index..].chars().next().map_or(1, char::len_utf8);
+                return Err(CalcError::UnknownOperator { span: index..index+len });
+            }
+        };
+        index += 1;
+        tokens.push((token, locat..index));
+    }
+    Ok(tokens)
+}
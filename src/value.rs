@@ -0,0 +1,146 @@
+use crate::context::Context;
+use crate::{Bigcomplex, Bignum};
+use rug::ops::Pow;
+use rug::{Complex, Float, Integer, Rational};
+use std::fmt;
+use std::ops::Neg;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Real(Float),
+    Complex(Complex),
+    Integer(Integer),
+    Rational(Rational),
+    Factors(Vec<(Integer, u32)>),
+}
+
+impl Value {
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Value::Real(v) => v.is_zero(),
+            Value::Complex(z) => z.real().is_zero() && z.imag().is_zero(),
+            Value::Integer(n) => *n == 0,
+            Value::Rational(r) => *r == 0,
+            Value::Factors(_) => false,
+        }
+    }
+
+    pub fn real(&self) -> Option<&Float> {
+        match self {
+            Value::Real(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_real(&self, ctx: &Context) -> Option<Float> {
+        match self {
+            Value::Real(v) => Some(v.clone()),
+            Value::Integer(n) => Some(ctx.float(n)),
+            Value::Rational(r) => Some(ctx.float(r)),
+            Value::Factors(_) => Some(ctx.float(&self.to_integer()?)),
+            Value::Complex(_) => None,
+        }
+    }
+
+    pub(crate) fn to_integer(&self) -> Option<Integer> {
+        match self {
+            Value::Integer(n) => Some(n.clone()),
+            Value::Real(v) if v.is_integer() => v.to_integer(),
+            Value::Rational(r) if *r.denom() == 1 => Some(r.numer().clone()),
+            Value::Factors(f) => Some(f.iter().fold(Integer::from(1), |n, (p, k)| n * Integer::from(p.pow(*k)))),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_rational(&self) -> Option<Rational> {
+        match self {
+            Value::Integer(n) => Some(Rational::from(n)),
+            Value::Factors(_) => Some(Rational::from(self.to_integer()?)),
+            Value::Rational(r) => Some(r.clone()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn into_complex(self, ctx: &Context) -> Complex {
+        match self {
+            Value::Real(v) => ctx.complex((v, 0)),
+            Value::Complex(z) => z,
+            Value::Integer(n) => ctx.complex((n, 0)),
+            Value::Rational(r) => ctx.complex((r, 0)),
+            Value::Factors(_) => ctx.complex((self.to_integer().unwrap_or_default(), 0)),
+        }
+    }
+
+    pub(crate) fn factors(&self, radix: i32) -> String {
+        match self {
+            Value::Factors(f) if !f.is_empty() => f.iter().map(|(p, k)| match k {
+                1 => p.to_string_radix(radix),
+                _ => format!("{}^{}", p.to_string_radix(radix), k),
+            }).collect::<Vec<_>>().join(" * "),
+            Value::Factors(_) => "1".to_string(),
+            _ => self.to_string(),
+        }
+    }
+
+    pub(crate) fn is_integer(&self) -> bool {
+        matches!(self, Value::Integer(_) | Value::Factors(_))
+    }
+
+    pub(crate) fn demote(z: Complex) -> Value {
+        if z.imag().is_zero() {
+            Value::Real(z.into_real_imag().0)
+        } else {
+            Value::Complex(z)
+        }
+    }
+}
+
+impl From<Float> for Value {
+    fn from(v: Float) -> Self {
+        Value::Real(v)
+    }
+}
+
+impl From<Integer> for Value {
+    fn from(n: Integer) -> Self {
+        Value::Integer(n)
+    }
+}
+
+impl From<Rational> for Value {
+    fn from(r: Rational) -> Self {
+        Value::Rational(r)
+    }
+}
+
+impl From<Complex> for Value {
+    fn from(z: Complex) -> Self {
+        Value::demote(z)
+    }
+}
+
+impl Neg for Value {
+    type Output = Value;
+
+    fn neg(self) -> Value {
+        match self {
+            Value::Real(v) => Value::Real(-v),
+            Value::Complex(z) => Value::Complex(-z),
+            Value::Integer(n) => Value::Integer(-n),
+            Value::Rational(r) => Value::Rational(-r),
+            Value::Factors(_) => Value::Integer(-self.to_integer().unwrap_or_default()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Real(v) => f.write_str(&v.to_string_round(None, 10)),
+            Value::Complex(z) => f.write_str(&z.to_string_round(None, 10)),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Rational(r) => write!(f, "{}", r),
+            Value::Factors(_) => f.write_str(&self.factors(10)),
+        }
+    }
+}